@@ -0,0 +1,265 @@
+use crate::inputs;
+use crate::parameters::Parameters;
+use crate::runner::{self, Part};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct Settings {
+    pub warmup: u32,
+    pub iterations: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        samples.sort_unstable();
+        let nearest_rank = |percentile: usize| {
+            let rank = (percentile * samples.len()).div_ceil(100);
+            samples[rank.max(1) - 1]
+        };
+        Some(Stats {
+            min: *samples.first()?,
+            median: nearest_rank(50),
+            p95: nearest_rank(95),
+            max: *samples.last()?,
+        })
+    }
+}
+
+pub struct Measurement {
+    pub day: u8,
+    pub part: Part,
+    pub stats: Result<Stats, String>,
+}
+
+pub fn measure_days(
+    year: u16,
+    days: RangeInclusive<u8>,
+    directory: &Path,
+    settings: &Settings,
+) -> Vec<Measurement> {
+    let mut measurements = Vec::new();
+    for day in days {
+        // Named inputs aren't benchmarked, only the first input found for a day.
+        let input = inputs::find(directory, day).and_then(|files| files[0].read());
+        for &part in &Part::ALL {
+            let stats = match &input {
+                Ok(input) => measure(year, day, part, input, settings),
+                Err(e) => Err(e.to_string()),
+            };
+            measurements.push(Measurement { day, part, stats });
+        }
+    }
+    measurements
+}
+
+/// Measures a part together with parsing its input.
+pub fn measure(
+    year: u16,
+    day: u8,
+    part: Part,
+    input: &str,
+    settings: &Settings,
+) -> Result<Stats, String> {
+    let solution = runner::get_solution(year, day)?;
+    let parameters = Parameters::defaults(solution.parameters());
+    let run = || {
+        solution
+            .parse(input)
+            .and_then(|parsed| parsed.run(part, &parameters))
+            .map_err(|e| e.to_string())
+    };
+    for _ in 0..settings.warmup {
+        run()?;
+    }
+    let mut samples = Vec::new();
+    for _ in 0..settings.iterations {
+        let start = Instant::now();
+        run()?;
+        samples.push(start.elapsed());
+    }
+    Stats::from_samples(samples).ok_or_else(|| "No iterations were ran".into())
+}
+
+pub type Baseline = HashMap<(u8, Part), Stats>;
+
+pub fn save_baseline(path: &Path, measurements: &[Measurement]) -> io::Result<()> {
+    let mut out = String::from("# day part min_ns median_ns p95_ns max_ns\n");
+    for measurement in measurements {
+        if let Ok(Stats {
+            min,
+            median,
+            p95,
+            max,
+        }) = measurement.stats
+        {
+            out += &format!(
+                "{} {} {} {} {} {}\n",
+                measurement.day,
+                measurement.part,
+                min.as_nanos(),
+                median.as_nanos(),
+                p95.as_nanos(),
+                max.as_nanos(),
+            );
+        }
+    }
+    fs::write(path, out)
+}
+
+pub fn load_baseline(path: &Path) -> Result<Baseline, Box<dyn Error>> {
+    parse_baseline(&fs::read_to_string(path)?)
+}
+
+fn parse_baseline(text: &str) -> Result<Baseline, Box<dyn Error>> {
+    let mut baseline = HashMap::new();
+    for line in text.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (day, part, nanos) = match fields.as_slice() {
+            [day, part, nanos @ ..] if nanos.len() == 4 => (day, part, nanos),
+            _ => return Err(format!("Malformed baseline line: {}", line).into()),
+        };
+        let part = Part::try_from(part.parse::<u8>()?)?;
+        let nanos = nanos
+            .iter()
+            .map(|n| n.parse().map(Duration::from_nanos))
+            .collect::<Result<Vec<_>, _>>()?;
+        let stats = Stats {
+            min: nanos[0],
+            median: nanos[1],
+            p95: nanos[2],
+            max: nanos[3],
+        };
+        baseline.insert((day.parse()?, part), stats);
+    }
+    Ok(baseline)
+}
+
+/// Writes a report, returning the number of parts which got slower than baseline by more than
+/// `threshold` percent when comparing medians.
+pub fn write_report(
+    mut out: impl Write,
+    measurements: &[Measurement],
+    baseline: Option<&Baseline>,
+    threshold: f64,
+) -> io::Result<usize> {
+    let mut regressions = 0;
+    write!(
+        out,
+        "{:>3}  {:>4}  {:>10}  {:>10}  {:>10}  {:>10}",
+        "Day", "Part", "Min", "Median", "P95", "Max"
+    )?;
+    if baseline.is_some() {
+        write!(out, "  {:>10}  {:>8}", "Baseline", "Change")?;
+    }
+    writeln!(out)?;
+    for Measurement { day, part, stats } in measurements {
+        write!(out, "{:>3}  {:>4}", day, part)?;
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(e) => {
+                writeln!(out, "  error: {}", e)?;
+                continue;
+            }
+        };
+        for duration in &[stats.min, stats.median, stats.p95, stats.max] {
+            write!(out, "  {:>10}", format!("{:.2?}", duration))?;
+        }
+        if let Some(old) = baseline.and_then(|b| b.get(&(*day, *part))) {
+            let change = (stats.median.as_secs_f64() / old.median.as_secs_f64() - 1.) * 100.;
+            write!(
+                out,
+                "  {:>10}  {:>+7.1}%",
+                format!("{:.2?}", old.median),
+                change
+            )?;
+            if change > threshold {
+                regressions += 1;
+                write!(out, "  REGRESSION")?;
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(regressions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_baseline, write_report, Measurement, Stats};
+    use crate::lines;
+    use crate::runner::Part;
+    use std::time::Duration;
+
+    fn stats(median: u64) -> Stats {
+        Stats {
+            min: Duration::from_millis(1),
+            median: Duration::from_millis(median),
+            p95: Duration::from_millis(median + 1),
+            max: Duration::from_millis(median + 2),
+        }
+    }
+
+    #[test]
+    fn computes_percentiles() {
+        let samples = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = Stats::from_samples(samples).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(50));
+        assert_eq!(stats.p95, Duration::from_millis(95));
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert_eq!(Stats::from_samples(Vec::new()), None);
+    }
+
+    #[test]
+    fn parses_baseline() {
+        let baseline = parse_baseline(lines!(
+            "# day part min_ns median_ns p95_ns max_ns"
+            "11 2 1000000 2000000 3000000 4000000"
+        ))
+        .unwrap();
+        assert_eq!(baseline[&(11, Part::Two)].median, Duration::from_millis(2));
+        assert!(parse_baseline("11 3 1 2 3 4").is_err());
+        assert!(parse_baseline("11 2 1 2 3").is_err());
+    }
+
+    #[test]
+    fn flags_regressions_above_threshold() {
+        let baseline = vec![((1, Part::One), stats(10)), ((1, Part::Two), stats(10))]
+            .into_iter()
+            .collect();
+        let measurements = [
+            Measurement {
+                day: 1,
+                part: Part::One,
+                stats: Ok(stats(11)),
+            },
+            Measurement {
+                day: 1,
+                part: Part::Two,
+                stats: Ok(stats(20)),
+            },
+        ];
+        let mut out = Vec::new();
+        let regressions = write_report(&mut out, &measurements, Some(&baseline), 15.).unwrap();
+        assert_eq!(regressions, 1);
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.lines().nth(1).unwrap().contains("REGRESSION"));
+        assert!(out.lines().nth(2).unwrap().ends_with("+100.0%  REGRESSION"));
+    }
+}
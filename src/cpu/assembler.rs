@@ -0,0 +1,366 @@
+use super::disassembler::Operand;
+use super::{Arithmetic, Instruction, InstructionKind, CPU};
+use crate::error::{Error, Span};
+use std::collections::HashMap;
+
+enum Statement<'a> {
+    Instruction(&'a str, Vec<&'a str>),
+    Jump(&'a str),
+}
+
+struct Assembler<'a> {
+    source: &'a str,
+    ip: Option<usize>,
+    registers: HashMap<&'a str, usize>,
+    constants: HashMap<&'a str, usize>,
+    labels: HashMap<&'a str, usize>,
+    statements: Vec<Statement<'a>>,
+}
+
+impl<'a> Assembler<'a> {
+    fn invalid(&self, token: &'a str, message: impl Into<String>) -> Error {
+        Error::Invalid {
+            day: None,
+            span: Span::new(self.source, token),
+            message: message.into(),
+        }
+    }
+
+    fn expected(&self, token: &'a str, expected: &str) -> Error {
+        Error::Syntax {
+            day: None,
+            span: Span::new(self.source, token),
+            expected: expected.into(),
+        }
+    }
+
+    /// Token right after the end of a line, for reporting missing operands.
+    fn end_of(line: &'a str) -> &'a str {
+        &line[line.len()..]
+    }
+
+    fn define(&mut self, token: &'a str, name: &'a str) -> Result<(), Error> {
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(self.expected(token, "a name"));
+        }
+        let taken = self.registers.contains_key(name)
+            || self.constants.contains_key(name)
+            || self.labels.contains_key(name)
+            || name == "ip"
+            || parse_register(name).is_some()
+            || kind(name).is_some();
+        if taken {
+            return Err(self.invalid(token, format!("{} is already defined", name)));
+        }
+        Ok(())
+    }
+
+    /// Reads labels, directives and statements.
+    fn read_line(&mut self, line: &'a str) -> Result<(), Error> {
+        let code = match line.find(';') {
+            Some(i) => &line[..i],
+            None => line,
+        };
+        let mut tokens: Vec<&'a str> = code.split_whitespace().collect();
+        while let Some(label) = tokens.first().and_then(|t| t.strip_suffix(':')) {
+            let token = tokens.remove(0);
+            self.define(token, label)?;
+            self.labels.insert(label, self.statements.len());
+        }
+        let (&first, operands) = match tokens.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+        let arity = |count: usize| {
+            if operands.len() == count {
+                Ok(())
+            } else {
+                let token = operands.get(count).copied().unwrap_or(Self::end_of(code));
+                Err(self.expected(token, &format!("{} operands", count)))
+            }
+        };
+        match first {
+            "#ip" => {
+                arity(1)?;
+                if self.ip.is_some() {
+                    return Err(self.invalid(first, "#ip is given twice"));
+                }
+                self.ip = Some(self.register(operands[0])?);
+            }
+            ".reg" => {
+                arity(2)?;
+                let register = self.register(operands[1])?;
+                self.define(operands[0], operands[0])?;
+                self.registers.insert(operands[0], register);
+            }
+            ".const" => {
+                arity(2)?;
+                let value = self.immediate(operands[1])?;
+                self.define(operands[0], operands[0])?;
+                self.constants.insert(operands[0], value);
+            }
+            "jmp" => {
+                arity(1)?;
+                self.statements.push(Statement::Jump(operands[0]));
+            }
+            _ if kind(first).is_some() => {
+                let count = match kind(first) {
+                    Some(InstructionKind::Seti) | Some(InstructionKind::Setr)
+                        if operands.len() == 2 =>
+                    {
+                        2
+                    }
+                    _ => 3,
+                };
+                arity(count)?;
+                self.statements
+                    .push(Statement::Instruction(first, operands.to_vec()));
+            }
+            _ => return Err(self.expected(first, "an instruction or directive")),
+        }
+        if self.ip.is_none() && !self.statements.is_empty() {
+            return Err(self.invalid(first, "#ip must come before instructions"));
+        }
+        Ok(())
+    }
+
+    fn register(&self, token: &'a str) -> Result<usize, Error> {
+        if let Some(&register) = self.registers.get(token) {
+            return Ok(register);
+        }
+        if let (Some(ip), "ip") = (self.ip, token) {
+            return Ok(ip);
+        }
+        match parse_register(token).or_else(|| token.parse().ok()) {
+            Some(register) if register < 6 => Ok(register),
+            Some(_) => Err(self.invalid(token, "registers go from r0 to r5")),
+            None => Err(self.expected(token, "a register")),
+        }
+    }
+
+    fn immediate(&self, token: &'a str) -> Result<usize, Error> {
+        if let Some(&value) = self.constants.get(token).or_else(|| self.labels.get(token)) {
+            return Ok(value);
+        }
+        token
+            .parse()
+            .map_err(|_| self.expected(token, "a number, constant or label"))
+    }
+
+    fn lower(&self, statement: &Statement<'a>) -> Result<Instruction, Error> {
+        match *statement {
+            Statement::Jump(label) => {
+                let target = *self
+                    .labels
+                    .get(label)
+                    .ok_or_else(|| self.expected(label, "a label"))?;
+                // The instruction pointer is incremented after the jump.
+                let value = target.checked_sub(1).ok_or_else(|| {
+                    self.invalid(label, "jumping to address 0 can't be written with seti")
+                })?;
+                Ok(Instruction {
+                    kind: InstructionKind::Seti,
+                    parameters: [value, 0, self.ip.unwrap()],
+                })
+            }
+            Statement::Instruction(name, ref operands) => {
+                let kind = kind(name).unwrap();
+                let operands: Vec<&'a str> = match operands.len() {
+                    2 => vec![operands[0], "0", operands[1]],
+                    _ => operands.clone(),
+                };
+                let template = Instruction {
+                    kind,
+                    parameters: [0; 3],
+                };
+                let (a, b) = template.operands();
+                let read = |operand, token| match operand {
+                    Operand::Register(_) => self.register(token),
+                    Operand::Immediate(_) => self.immediate(token),
+                };
+                let a = read(a, operands[0])?;
+                let b = match b {
+                    Some(b) => read(b, operands[1])?,
+                    None => self
+                        .immediate(operands[1])
+                        .or_else(|_| self.register(operands[1]))?,
+                };
+                let c = self.register(operands[2])?;
+                Ok(Instruction {
+                    kind,
+                    parameters: [a, b, c],
+                })
+            }
+        }
+    }
+}
+
+fn parse_register(token: &str) -> Option<usize> {
+    token.strip_prefix('r')?.parse().ok()
+}
+
+fn kind(name: &str) -> Option<InstructionKind> {
+    use self::InstructionKind::*;
+    Some(match name {
+        "addr" => Addr,
+        "addi" => Addi,
+        "mulr" => Mulr,
+        "muli" => Muli,
+        "banr" => Banr,
+        "bani" => Bani,
+        "borr" => Borr,
+        "bori" => Bori,
+        "setr" => Setr,
+        "seti" => Seti,
+        "gtir" => Gtir,
+        "gtri" => Gtri,
+        "gtrr" => Gtrr,
+        "eqir" => Eqir,
+        "eqri" => Eqri,
+        "eqrr" => Eqrr,
+        _ => return None,
+    })
+}
+
+/// Assembles a program, starting it with given register values. The dialect is a superset of
+/// the puzzle format:
+///
+/// ```text
+/// #ip r4                  ; register bound to the instruction pointer, also called ip
+/// .reg counter r1         ; register alias
+/// .const limit 10         ; named immediate value
+///     seti 0 counter      ; the ignored operand of seti and setr can be left out
+/// loop:
+///     addi counter 1 counter
+///     gtri counter limit r3
+///     addr r3 ip ip
+///     jmp loop            ; lowered to seti with the address before the label
+/// ```
+///
+/// Registers are written `r0` to `r5`, as an alias or as a bare number. Immediate values are
+/// numbers, constants or labels, which stand for their address.
+pub fn assemble(source: &str, registers: [usize; 6]) -> Result<CPU, Error> {
+    let mut assembler = Assembler {
+        source,
+        ip: None,
+        registers: HashMap::new(),
+        constants: HashMap::new(),
+        labels: HashMap::new(),
+        statements: Vec::new(),
+    };
+    for line in source.lines() {
+        assembler.read_line(line)?;
+    }
+    let ip = assembler
+        .ip
+        .ok_or_else(|| assembler.expected(Assembler::end_of(source), "#ip"))?;
+    let instructions = assembler
+        .statements
+        .iter()
+        .map(|statement| assembler.lower(statement))
+        .collect::<Result<_, _>>()?;
+    Ok(CPU {
+        ip: Some(ip),
+        pointer: 0,
+        registers,
+        instructions,
+        arithmetic: Arithmetic::default(),
+        overflow: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::assemble;
+    use crate::cpu::{get_cpu, Backend};
+    use crate::lines;
+
+    #[test]
+    fn lowers_to_plain_instructions() {
+        let cpu = assemble(
+            lines!(
+                "; Counts r0 up to a limit."
+                ""
+                "#ip r4"
+                ".reg counter r0"
+                ".const limit 3"
+                "    seti 0 counter"
+                "loop: addi counter 1 counter  ; next"
+                "    gtri counter limit r3"
+                "    addr r3 ip ip"
+                "    jmp loop"
+                "end:"
+                "    seti end 0 r1"
+            )
+            .trim_end(),
+            [0; 6],
+        )
+        .unwrap();
+        let plain = get_cpu(
+            lines!(
+                "#ip 4"
+                "seti 0 0 0"
+                "addi 0 1 0"
+                "gtri 0 3 3"
+                "addr 3 4 4"
+                "seti 0 0 4"
+                "seti 5 0 1"
+            ),
+            [0; 6],
+        )
+        .unwrap();
+        assert_eq!(cpu.instructions(), plain.instructions());
+        assert_eq!(cpu.ip_register(), Ok(4));
+        let mut cpu = cpu;
+        assert_eq!(cpu.run(), Ok(4));
+        assert_eq!(cpu.registers[1], 5);
+    }
+
+    #[test]
+    fn accepts_puzzle_inputs() {
+        let input = include_str!("../day19/input");
+        let cpu = assemble(input, [0; 6]).unwrap();
+        assert_eq!(
+            cpu.instructions(),
+            get_cpu(input, [0; 6]).unwrap().instructions()
+        );
+    }
+
+    #[test]
+    fn locates_errors() {
+        let error = |source| assemble(source, [0; 6]).unwrap_err().to_string();
+        assert_eq!(
+            error("#ip 4\naddi r1 1 r9"),
+            "line 2, column 11: registers go from r0 to r5"
+        );
+        assert_eq!(
+            error("#ip 4\njmp nowhere"),
+            "line 2, column 5: expected a label, found 'n'"
+        );
+        assert_eq!(
+            error("#ip 4\naddi r1 1"),
+            "line 2, column 10: expected 3 operands, found end of line"
+        );
+        assert_eq!(
+            error("#ip 4\nloop:\nloop: seti 1 r0"),
+            "line 3, column 1: loop is already defined"
+        );
+        assert_eq!(
+            error("seti 1 r0"),
+            "line 1, column 1: #ip must come before instructions"
+        );
+        assert_eq!(
+            error("#ip 4\nstart: jmp start"),
+            "line 2, column 12: jumping to address 0 can't be written with seti"
+        );
+        assert_eq!(
+            error("#ip 4\nmove 1 r0"),
+            "line 2, column 1: expected an instruction or directive, found 'm'"
+        );
+    }
+}
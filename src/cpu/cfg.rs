@@ -0,0 +1,551 @@
+use super::disassembler::{Disassembler, Jump, Target};
+use super::CPU;
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// Where control goes after a block.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Successor {
+    Block(usize),
+    /// The program halts.
+    Exit,
+    /// The destination is computed from registers and isn't known statically.
+    Unknown,
+}
+
+/// Instructions which always run one after another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub addresses: Range<usize>,
+    pub successors: Vec<Successor>,
+    pub predecessors: Vec<usize>,
+}
+
+/// Loop formed by back edges to a header dominating them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loop {
+    pub header: usize,
+    /// Blocks of the loop, including the header.
+    pub body: BTreeSet<usize>,
+    /// Blocks jumping back to the header.
+    pub latches: Vec<usize>,
+    /// Number of loops containing this one, 1 for outermost loops.
+    pub depth: usize,
+}
+
+/// Control-flow graph of a program. Blocks are numbered in address order, the first one is
+/// the entry.
+pub struct Cfg {
+    blocks: Vec<Block>,
+    dominators: Vec<Option<usize>>,
+    /// Indexed like blocks plus one for the exit.
+    post_dominators: Vec<Option<usize>>,
+    loops: Vec<Loop>,
+}
+
+impl Cfg {
+    pub fn new(cpu: &CPU) -> Self {
+        let disassembler = Disassembler::new(cpu);
+        let length = cpu.instructions().len();
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for address in 0..length {
+            if let Some(jump) = disassembler.jump(address) {
+                leaders.insert(address + 1);
+                match jump {
+                    Jump::Goto(Target::Address(target))
+                    | Jump::Branch {
+                        taken: Target::Address(target),
+                        ..
+                    } => {
+                        leaders.insert(target);
+                    }
+                    Jump::Offset { base, .. } => {
+                        leaders.insert(base + 1);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let starts: Vec<_> = leaders.into_iter().filter(|&a| a < length).collect();
+        let block_of = |address: usize| match starts.binary_search(&address) {
+            Ok(block) => Successor::Block(block),
+            Err(_) => Successor::Exit,
+        };
+        let mut blocks: Vec<_> = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(length);
+                let last = end - 1;
+                let target = |target| match target {
+                    Target::Address(address) => block_of(address),
+                    Target::Halt => Successor::Exit,
+                };
+                let mut successors = match disassembler.jump(last) {
+                    None => vec![block_of(end)],
+                    Some(Jump::Goto(t)) => vec![target(t)],
+                    Some(Jump::Branch { taken, .. }) => vec![block_of(end), target(taken)],
+                    // Offsets are usually flags selecting one of two paths, other values are
+                    // still possible.
+                    Some(Jump::Offset { base, .. }) => {
+                        vec![block_of(base), block_of(base + 1), Successor::Unknown]
+                    }
+                    Some(Jump::Computed) => vec![Successor::Unknown],
+                };
+                successors.dedup();
+                Block {
+                    addresses: start..end,
+                    successors,
+                    predecessors: Vec::new(),
+                }
+            })
+            .collect();
+        for block in 0..blocks.len() {
+            for successor in blocks[block].successors.clone() {
+                if let Successor::Block(successor) = successor {
+                    blocks[successor].predecessors.push(block);
+                }
+            }
+        }
+        let successors: Vec<Vec<usize>> = blocks
+            .iter()
+            .map(|b| {
+                b.successors
+                    .iter()
+                    .filter_map(|&s| match s {
+                        Successor::Block(s) => Some(s),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        let predecessors: Vec<_> = blocks.iter().map(|b| b.predecessors.clone()).collect();
+        let dominators = immediate_dominators(0, &successors, &predecessors);
+        // Post-dominators are dominators of the reversed graph, with a virtual node for the
+        // exit. Unknown destinations only lead to the exit when nothing else is known.
+        let exit = blocks.len();
+        let mut reversed_successors = vec![Vec::new(); exit + 1];
+        let mut reversed_predecessors = vec![Vec::new(); exit + 1];
+        for (block, b) in blocks.iter().enumerate() {
+            let known = b
+                .successors
+                .iter()
+                .any(|s| matches!(s, Successor::Block(_)));
+            for &successor in &b.successors {
+                let node = match successor {
+                    Successor::Block(s) => s,
+                    Successor::Exit => exit,
+                    Successor::Unknown if !known => exit,
+                    Successor::Unknown => continue,
+                };
+                if !reversed_predecessors[block].contains(&node) {
+                    reversed_predecessors[block].push(node);
+                    reversed_successors[node].push(block);
+                }
+            }
+        }
+        let post_dominators =
+            immediate_dominators(exit, &reversed_successors, &reversed_predecessors);
+        let mut cfg = Cfg {
+            blocks,
+            dominators,
+            post_dominators,
+            loops: Vec::new(),
+        };
+        cfg.loops = cfg.find_loops();
+        cfg
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Block containing an address.
+    pub fn block_at(&self, address: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|b| b.addresses.contains(&address))
+    }
+
+    /// Immediate dominator of a block, `None` for the entry and unreachable blocks.
+    pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+        self.dominators[block].filter(|&d| d != block)
+    }
+
+    /// First block every path from `block` to the exit goes through, `Exit` if there is none
+    /// and `None` for blocks which never reach the exit.
+    pub fn immediate_post_dominator(&self, block: usize) -> Option<Successor> {
+        let exit = self.blocks.len();
+        self.post_dominators[block].map(|d| {
+            if d == exit {
+                Successor::Exit
+            } else {
+                Successor::Block(d)
+            }
+        })
+    }
+
+    pub fn is_reachable(&self, block: usize) -> bool {
+        self.dominators[block].is_some()
+    }
+
+    /// Whether every path from the entry to `block` goes through `dominator`.
+    pub fn dominates(&self, dominator: usize, mut block: usize) -> bool {
+        if !self.is_reachable(block) {
+            return false;
+        }
+        loop {
+            if block == dominator {
+                return true;
+            }
+            match self.immediate_dominator(block) {
+                Some(parent) => block = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Natural loops, ordered by header.
+    pub fn loops(&self) -> &[Loop] {
+        &self.loops
+    }
+
+    /// Loops not containing other loops, where programs spend most of their time.
+    pub fn innermost_loops(&self) -> impl Iterator<Item = &Loop> {
+        self.loops.iter().filter(move |l| {
+            !self
+                .loops
+                .iter()
+                .any(|other| other.header != l.header && l.body.contains(&other.header))
+        })
+    }
+
+    fn find_loops(&self) -> Vec<Loop> {
+        let mut loops: Vec<Loop> = Vec::new();
+        for (latch, block) in self.blocks.iter().enumerate() {
+            for &successor in &block.successors {
+                let header = match successor {
+                    Successor::Block(header) if self.dominates(header, latch) => header,
+                    _ => continue,
+                };
+                let mut body = BTreeSet::new();
+                body.insert(header);
+                let mut stack = vec![latch];
+                while let Some(block) = stack.pop() {
+                    if body.insert(block) {
+                        stack.extend(&self.blocks[block].predecessors);
+                    }
+                }
+                match loops.iter_mut().find(|l| l.header == header) {
+                    Some(existing) => {
+                        existing.body.extend(body);
+                        existing.latches.push(latch);
+                    }
+                    None => loops.push(Loop {
+                        header,
+                        body,
+                        latches: vec![latch],
+                        depth: 0,
+                    }),
+                }
+            }
+        }
+        loops.sort_by_key(|l| l.header);
+        let headers: Vec<_> = loops.iter().map(|l| (l.header, l.body.clone())).collect();
+        for l in &mut loops {
+            l.depth = headers
+                .iter()
+                .filter(|(_, body)| body.contains(&l.header))
+                .count();
+        }
+        loops
+    }
+
+    /// Writes the graph in Graphviz DOT format. Loop headers are highlighted and back edges
+    /// drawn in bold.
+    pub fn write_dot(&self, cpu: &CPU, mut out: impl Write) -> io::Result<()> {
+        let disassembler = Disassembler::new(cpu);
+        writeln!(out, "digraph cfg {{")?;
+        writeln!(out, "    node [shape=box, fontname=monospace];")?;
+        for (i, block) in self.blocks.iter().enumerate() {
+            let mut label = format!(
+                "B{} ({}..{})\\l",
+                i, block.addresses.start, block.addresses.end
+            );
+            for address in block.addresses.clone() {
+                let statement = disassembler.statement(address).unwrap_or_default();
+                label += &format!("{:>3}: {}\\l", address, statement);
+            }
+            let style = match self.loops.iter().find(|l| l.header == i) {
+                Some(l) => format!(
+                    ", style=filled, fillcolor=\"/blues9/{}\"",
+                    (l.depth + 1).min(9)
+                ),
+                None => String::new(),
+            };
+            writeln!(out, "    b{} [label=\"{}\"{}];", i, escape(&label), style)?;
+        }
+        let mut exits = false;
+        let mut unknown = false;
+        for (i, block) in self.blocks.iter().enumerate() {
+            for &successor in &block.successors {
+                match successor {
+                    Successor::Block(to) => {
+                        let back = self
+                            .loops
+                            .iter()
+                            .any(|l| l.header == to && l.latches.contains(&i));
+                        let style = if back { " [style=bold]" } else { "" };
+                        writeln!(out, "    b{} -> b{}{};", i, to, style)?;
+                    }
+                    Successor::Exit => {
+                        exits = true;
+                        writeln!(out, "    b{} -> exit;", i)?;
+                    }
+                    Successor::Unknown => {
+                        unknown = true;
+                        writeln!(out, "    b{} -> unknown [style=dashed];", i)?;
+                    }
+                }
+            }
+        }
+        if exits {
+            writeln!(out, "    exit [shape=doublecircle, label=\"halt\"];")?;
+        }
+        if unknown {
+            writeln!(out, "    unknown [shape=circle, label=\"?\"];")?;
+        }
+        writeln!(out, "}}")
+    }
+
+    /// Writes blocks, dominators and loops as text.
+    pub fn write_summary(&self, mut out: impl Write) -> io::Result<()> {
+        for (i, block) in self.blocks.iter().enumerate() {
+            let successors: Vec<_> = block
+                .successors
+                .iter()
+                .map(|s| match s {
+                    Successor::Block(b) => format!("B{}", b),
+                    Successor::Exit => "halt".into(),
+                    Successor::Unknown => "?".into(),
+                })
+                .collect();
+            let dominator = match self.immediate_dominator(i) {
+                Some(d) => format!("B{}", d),
+                None if self.is_reachable(i) => "entry".into(),
+                None => "unreachable".into(),
+            };
+            writeln!(
+                out,
+                "B{} {}..{} -> {} (idom {})",
+                i,
+                block.addresses.start,
+                block.addresses.end,
+                successors.join(", "),
+                dominator
+            )?;
+        }
+        for l in &self.loops {
+            let body: Vec<_> = l.body.iter().map(|b| format!("B{}", b)).collect();
+            let innermost = self.innermost_loops().any(|i| i.header == l.header);
+            writeln!(
+                out,
+                "loop at B{} (depth {}{}): {}",
+                l.header,
+                l.depth,
+                if innermost { ", innermost" } else { "" },
+                body.join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('"', "\\\"")
+}
+
+/// Computes immediate dominators with the iterative algorithm by Cooper, Harvey and Kennedy.
+/// The graph is given as adjacency lists, the entry is its own dominator and nodes it doesn't
+/// reach have none.
+fn immediate_dominators(
+    entry: usize,
+    successors: &[Vec<usize>],
+    predecessors: &[Vec<usize>],
+) -> Vec<Option<usize>> {
+    let count = successors.len();
+    let mut dominators: Vec<Option<usize>> = vec![None; count];
+    if entry >= count {
+        return dominators;
+    }
+    // Reverse postorder of nodes reachable from the entry.
+    let mut postorder = Vec::new();
+    let mut visited = vec![false; count];
+    let mut stack = vec![(entry, 0)];
+    visited[entry] = true;
+    while let Some((node, next)) = stack.pop() {
+        match successors[node].get(next) {
+            Some(&successor) => {
+                stack.push((node, next + 1));
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            }
+            None => postorder.push(node),
+        }
+    }
+    let mut order = vec![usize::MAX; count];
+    for (i, &node) in postorder.iter().enumerate() {
+        order[node] = i;
+    }
+    dominators[entry] = Some(entry);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in postorder.iter().rev().skip(1) {
+            let mut new: Option<usize> = None;
+            for &predecessor in &predecessors[node] {
+                if dominators[predecessor].is_none() {
+                    continue;
+                }
+                new = Some(match new {
+                    None => predecessor,
+                    Some(mut a) => {
+                        let mut b = predecessor;
+                        while a != b {
+                            while order[a] < order[b] {
+                                a = dominators[a].unwrap();
+                            }
+                            while order[b] < order[a] {
+                                b = dominators[b].unwrap();
+                            }
+                        }
+                        a
+                    }
+                });
+            }
+            if new.is_some() && dominators[node] != new {
+                dominators[node] = new;
+                changed = true;
+            }
+        }
+    }
+    dominators
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cfg, Successor};
+    use crate::cpu::get_cpu;
+    use crate::lines;
+
+    #[test]
+    fn finds_nested_loops() {
+        // Day 19 inner loops, counting divisors of r2 into r0.
+        let cpu = get_cpu(
+            lines!(
+                "#ip 4"
+                "seti 1 0 1"
+                "seti 1 0 5"
+                "mulr 1 5 3"
+                "eqrr 3 2 3"
+                "addr 3 4 4"
+                "addi 4 1 4"
+                "addr 1 0 0"
+                "addi 5 1 5"
+                "gtrr 5 2 3"
+                "addr 4 3 4"
+                "seti 1 0 4"
+                "addi 1 1 1"
+                "gtrr 1 2 3"
+                "addr 3 4 4"
+                "seti 0 0 4"
+                "mulr 4 4 4"
+            ),
+            [0; 6],
+        )
+        .unwrap();
+        let cfg = Cfg::new(&cpu);
+        let ranges: Vec<_> = cfg.blocks().iter().map(|b| b.addresses.clone()).collect();
+        assert_eq!(
+            ranges,
+            [
+                0..1,
+                1..2,
+                2..5,
+                5..6,
+                6..7,
+                7..10,
+                10..11,
+                11..14,
+                14..15,
+                15..16
+            ]
+        );
+        assert_eq!(
+            cfg.blocks()[2].successors,
+            [Successor::Block(3), Successor::Block(4)]
+        );
+        assert_eq!(cfg.blocks()[9].successors, [Successor::Exit]);
+        assert_eq!(cfg.immediate_dominator(4), Some(2));
+        assert_eq!(cfg.immediate_dominator(0), None);
+        assert!(cfg.dominates(1, 6));
+        assert!(!cfg.dominates(3, 4));
+        assert_eq!(cfg.immediate_post_dominator(2), Some(Successor::Block(5)));
+        assert_eq!(cfg.immediate_post_dominator(7), Some(Successor::Block(9)));
+        assert_eq!(cfg.immediate_post_dominator(9), Some(Successor::Exit));
+        let loops: Vec<_> = cfg
+            .loops()
+            .iter()
+            .map(|l| {
+                (
+                    l.header,
+                    l.body.iter().copied().collect::<Vec<_>>(),
+                    l.depth,
+                )
+            })
+            .collect();
+        assert_eq!(
+            loops,
+            [
+                (1, vec![1, 2, 3, 4, 5, 6, 7, 8], 1),
+                (2, vec![2, 3, 4, 5, 6], 2),
+            ]
+        );
+        let innermost: Vec<_> = cfg.innermost_loops().map(|l| l.header).collect();
+        assert_eq!(innermost, [2]);
+    }
+
+    #[test]
+    fn marks_computed_jumps() {
+        let cpu = get_cpu(
+            lines!(
+                "#ip 0"
+                "addr 0 1 0"
+                "seti 0 0 0"
+                "setr 2 0 0"
+            ),
+            [0; 6],
+        )
+        .unwrap();
+        let cfg = Cfg::new(&cpu);
+        assert_eq!(
+            cfg.blocks()[0].successors,
+            [Successor::Block(1), Successor::Block(2), Successor::Unknown]
+        );
+        assert_eq!(cfg.blocks()[2].successors, [Successor::Unknown]);
+        assert_eq!(cfg.immediate_dominator(2), Some(0));
+        assert_eq!(cfg.loops().len(), 1);
+        assert_eq!(cfg.loops()[0].latches, [1]);
+        let mut out = Vec::new();
+        cfg.write_dot(&cpu, &mut out).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    b0 -> unknown [style=dashed];\n"));
+        assert!(dot.contains("    b1 -> b1 [style=bold];\n"));
+        assert!(dot.contains("label=\"B2 (2..3)\\l  2: ip = r2\\l\""));
+    }
+}
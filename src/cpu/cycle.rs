@@ -0,0 +1,152 @@
+use super::{Backend, Snapshot};
+use std::collections::HashMap;
+
+/// Repetition found in the states of a machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    /// Step at which the machine first was in the state it comes back to.
+    pub entry: u64,
+    /// Number of steps before the state repeats.
+    pub length: u64,
+    /// Values of the watched register in the recorded states, up to the repetition.
+    pub values: Vec<usize>,
+}
+
+/// Finds when a machine enters a cycle by hashing its states.
+#[derive(Clone, Debug, Default)]
+pub struct CycleDetector<const N: usize = 6> {
+    address: Option<usize>,
+    register: Option<usize>,
+    seen: HashMap<Snapshot<N>, u64>,
+    values: Vec<usize>,
+}
+
+impl<const N: usize> CycleDetector<N> {
+    pub fn new() -> Self {
+        CycleDetector {
+            address: None,
+            register: None,
+            seen: HashMap::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Only records states at an address, which every cycle of a loop containing it goes
+    /// through. This saves a lot of memory on long loops.
+    pub fn at_address(mut self, address: usize) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Collects the values a register takes in the recorded states.
+    pub fn watching(mut self, register: usize) -> Self {
+        self.register = Some(register);
+        self
+    }
+
+    /// Records the state of the machine after a number of steps, returning the cycle once a
+    /// state comes back.
+    pub fn record(&mut self, step: u64, snapshot: &Snapshot<N>) -> Option<Cycle> {
+        if self
+            .address
+            .is_some_and(|address| address != snapshot.address)
+        {
+            return None;
+        }
+        if let Some(&entry) = self.seen.get(snapshot) {
+            return Some(Cycle {
+                entry,
+                length: step - entry,
+                values: self.values.clone(),
+            });
+        }
+        self.seen.insert(*snapshot, step);
+        if let Some(register) = self.register {
+            self.values.push(snapshot.registers[register]);
+        }
+        None
+    }
+}
+
+impl CycleDetector {
+    /// Runs a backend until it enters a cycle, `None` if it halts instead.
+    pub fn run(&mut self, backend: &mut dyn Backend) -> Option<Cycle> {
+        let mut step = 0;
+        loop {
+            if let Some(cycle) = self.record(step, &backend.snapshot()) {
+                return Some(cycle);
+            }
+            if !backend.step() {
+                return None;
+            }
+            step += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cycle, CycleDetector};
+    use crate::cpu::{get_cpu, load_numeric, InstructionKind};
+    use crate::lines;
+
+    // r1 counts up to 3 and back to 0 forever, after setting r2.
+    const PROGRAM: &str = lines!(
+        "#ip 0"
+        "seti 7 0 2"
+        "addi 1 1 1"
+        "eqri 1 3 3"
+        "addr 0 3 0"
+        "seti 0 0 0"
+        "seti 0 0 1"
+        "seti 0 0 0"
+    );
+
+    #[test]
+    fn finds_cycles() {
+        let mut cpu = get_cpu(PROGRAM, [0; 6]).unwrap();
+        let cycle = CycleDetector::new().watching(1).run(&mut cpu);
+        // r3 is still set when r1 is back to 1, so the first state repeated is at address 3.
+        assert_eq!(cycle.map(|c| (c.entry, c.length)), Some((3, 13)));
+        let mut cpu = get_cpu(PROGRAM, [0; 6]).unwrap();
+        let cycle = CycleDetector::new().at_address(3).watching(1).run(&mut cpu);
+        assert_eq!(
+            cycle,
+            Some(Cycle {
+                entry: 3,
+                length: 13,
+                values: vec![1, 2, 3],
+            })
+        );
+    }
+
+    #[test]
+    fn halting_has_no_cycle() {
+        let mut cpu = get_cpu(lines!("#ip 0" "addi 1 1 1"), [0; 6]).unwrap();
+        assert_eq!(CycleDetector::new().run(&mut cpu), None);
+        let opcodes = vec![(0, InstructionKind::Addi)].into_iter().collect();
+        let mut cpu = load_numeric(&[[0, 1, 1, 1]], &opcodes, [0; 6]).unwrap();
+        assert_eq!(CycleDetector::new().run(&mut cpu), None);
+    }
+
+    #[test]
+    fn restores_snapshots() {
+        let mut cpu = get_cpu(PROGRAM, [0; 6]).unwrap();
+        for _ in 0..4 {
+            cpu.step();
+        }
+        let snapshot = cpu.snapshot();
+        assert_eq!(snapshot.address, 4);
+        let trace = |cpu: &mut crate::cpu::CPU| {
+            (0..10)
+                .map(|_| {
+                    cpu.step();
+                    cpu.snapshot()
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = trace(&mut cpu);
+        cpu.restore(&snapshot);
+        assert_eq!(trace(&mut cpu), first);
+    }
+}
@@ -0,0 +1,463 @@
+use super::disassembler::Disassembler;
+use super::{Overflow, CPU};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn parse(text: &str) -> Result<Self, String> {
+        Ok(match text {
+            "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            _ => return Err(format!("Unknown comparison {:?}", text)),
+        })
+    }
+
+    fn holds(self, left: usize, right: usize) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        })
+    }
+}
+
+/// Condition checked before running each instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Point {
+    /// Stop when the instruction pointer reaches an address.
+    Address(usize),
+    /// Stop when a register compares to a value.
+    Register {
+        register: usize,
+        comparison: Comparison,
+        value: usize,
+    },
+    /// Stop when a register changes.
+    Watch(usize),
+}
+
+/// Why running stopped.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// All requested steps were ran.
+    Done,
+    /// The instruction pointer left the program.
+    Halted,
+    /// An instruction overflowed, which stops the program.
+    Overflow(Overflow),
+    /// A breakpoint or a watchpoint with this number triggered.
+    Point(usize),
+}
+
+/// Runs a program under control of commands, like `step 10` or `break r3 == 5`.
+pub struct Debugger {
+    cpu: CPU,
+    points: Vec<(usize, Point)>,
+    next_point: usize,
+    steps: u64,
+}
+
+const HELP: &str = "\
+step [N]             run N instructions, 1 by default
+continue [N]         run until a breakpoint, a watchpoint or the end, at most N instructions
+break ADDRESS        stop when the instruction pointer reaches an address
+break REG OP VALUE   stop when a register compares to a value, OP is one of == != < <= > >=
+watch REG            stop when a register changes
+delete ID            remove a breakpoint or a watchpoint
+points               list breakpoints and watchpoints
+registers            print registers
+set REG VALUE        change a register
+list [N]             disassemble N instructions from the current one, 1 by default
+help                 print this help
+quit                 stop debugging
+Registers are written as r0 to r5, or ip for the one bound to the instruction pointer. An empty
+line repeats the previous command.";
+
+impl Debugger {
+    pub fn new(cpu: CPU) -> Self {
+        Debugger {
+            cpu,
+            points: Vec::new(),
+            next_point: 1,
+            steps: 0,
+        }
+    }
+
+    pub fn cpu(&self) -> &CPU {
+        &self.cpu
+    }
+
+    /// Number of instructions ran so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    fn address(&self) -> usize {
+        self.cpu.address()
+    }
+
+    pub fn add_point(&mut self, point: Point) -> usize {
+        let id = self.next_point;
+        self.next_point += 1;
+        self.points.push((id, point));
+        id
+    }
+
+    pub fn remove_point(&mut self, id: usize) -> bool {
+        let count = self.points.len();
+        self.points.retain(|&(i, _)| i != id);
+        self.points.len() != count
+    }
+
+    fn triggered(&self, before: &[usize; 6], stop_on_points: bool) -> Option<usize> {
+        self.points.iter().find_map(|&(id, point)| {
+            let hit = match point {
+                _ if !stop_on_points && !matches!(point, Point::Watch(_)) => false,
+                Point::Address(address) => self.address() == address,
+                Point::Register {
+                    register,
+                    comparison,
+                    value,
+                } => comparison.holds(self.cpu.registers[register], value),
+                Point::Watch(register) => before[register] != self.cpu.registers[register],
+            };
+            Some(id).filter(|_| hit)
+        })
+    }
+
+    /// Runs at most `limit` instructions. Points are checked after every instruction, so
+    /// running from a breakpoint doesn't stop on it right away. With `stop_on_points` unset,
+    /// only watchpoints stop running.
+    pub fn run(&mut self, limit: u64, stop_on_points: bool) -> Stop {
+        for _ in 0..limit {
+            let before = self.cpu.registers;
+            if !self.cpu.step() {
+                return match self.cpu.overflow() {
+                    Some(overflow) => Stop::Overflow(overflow),
+                    None => Stop::Halted,
+                };
+            }
+            self.steps += 1;
+            if self.cpu.current_instruction().is_none() {
+                return Stop::Halted;
+            }
+            if let Some(id) = self.triggered(&before, stop_on_points) {
+                return Stop::Point(id);
+            }
+        }
+        Stop::Done
+    }
+
+    fn register(&self, text: &str) -> Result<usize, String> {
+        if text == "ip" {
+            return self.cpu.ip_register().map_err(|e| e.to_string());
+        }
+        text.strip_prefix('r')
+            .and_then(|n| n.parse().ok())
+            .filter(|&n: &usize| n < self.cpu.registers.len())
+            .ok_or_else(|| format!("Unknown register {:?}", text))
+    }
+
+    fn describe(&self, point: Point) -> String {
+        let disassembler = Disassembler::new(&self.cpu);
+        match point {
+            Point::Address(address) => format!("break at {}", address),
+            Point::Register {
+                register,
+                comparison,
+                value,
+            } => format!(
+                "break when {} {} {}",
+                disassembler.name(register),
+                comparison,
+                value
+            ),
+            Point::Watch(register) => format!("watch {}", disassembler.name(register)),
+        }
+    }
+
+    fn write_registers(&self, mut out: impl Write) -> io::Result<()> {
+        let disassembler = Disassembler::new(&self.cpu);
+        let registers: Vec<_> = self
+            .cpu
+            .registers
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!("{}={}", disassembler.name(i), value))
+            .collect();
+        writeln!(out, "{}", registers.join(" "))
+    }
+
+    fn write_instructions(&self, mut out: impl Write, count: usize) -> io::Result<()> {
+        let disassembler = Disassembler::new(&self.cpu);
+        let start = self.address();
+        for address in start..start.saturating_add(count) {
+            let instruction = match self.cpu.instructions().get(address) {
+                Some(instruction) => instruction,
+                None => break,
+            };
+            let marker = if address == start { "=>" } else { "  " };
+            write!(
+                out,
+                "{} {}: {}  {}",
+                marker,
+                address,
+                instruction,
+                disassembler.statement(address).unwrap_or_default()
+            )?;
+            match disassembler.describe_jump(address) {
+                Some(jump) => writeln!(out, "  ; {}", jump)?,
+                None => writeln!(out)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_stop(&self, mut out: impl Write, stop: Stop) -> io::Result<()> {
+        match stop {
+            Stop::Done => {}
+            Stop::Halted => {
+                writeln!(out, "halted after {} steps", self.steps)?;
+                return self.write_registers(out);
+            }
+            Stop::Overflow(overflow) => {
+                writeln!(out, "{} after {} steps", overflow, self.steps)?;
+                return self.write_registers(out);
+            }
+            Stop::Point(id) => {
+                let point = self.points.iter().find(|p| p.0 == id).map(|p| p.1);
+                if let Some(point) = point {
+                    writeln!(
+                        out,
+                        "#{} {}, after {} steps",
+                        id,
+                        self.describe(point),
+                        self.steps
+                    )?;
+                }
+            }
+        }
+        self.write_registers(&mut out)?;
+        self.write_instructions(out, 1)
+    }
+
+    /// Runs a single command, returning `false` when debugging should end.
+    pub fn command(&mut self, line: &str, mut out: impl Write) -> Result<bool, Box<dyn Error>> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        let count = |i: usize, default| -> Result<u64, String> {
+            words.get(i).map_or(Ok(default), |w| {
+                w.parse().map_err(|_| format!("Invalid count {:?}", w))
+            })
+        };
+        match words.as_slice() {
+            [] => {}
+            ["step" | "s", ..] => {
+                let stop = self.run(count(1, 1)?, false);
+                self.write_stop(out, stop)?;
+            }
+            ["continue" | "c", ..] => {
+                let stop = self.run(count(1, u64::MAX)?, true);
+                self.write_stop(out, stop)?;
+            }
+            ["break" | "b", address] => {
+                let address = address
+                    .parse()
+                    .map_err(|_| format!("Invalid address {:?}", address))?;
+                let id = self.add_point(Point::Address(address));
+                writeln!(out, "#{} {}", id, self.describe(Point::Address(address)))?;
+            }
+            ["break" | "b", register, comparison, value] => {
+                let point = Point::Register {
+                    register: self.register(register)?,
+                    comparison: Comparison::parse(comparison)?,
+                    value: value
+                        .parse()
+                        .map_err(|_| format!("Invalid value {:?}", value))?,
+                };
+                let id = self.add_point(point);
+                writeln!(out, "#{} {}", id, self.describe(point))?;
+            }
+            ["watch" | "w", register] => {
+                let point = Point::Watch(self.register(register)?);
+                let id = self.add_point(point);
+                writeln!(out, "#{} {}", id, self.describe(point))?;
+            }
+            ["delete" | "d", id] => {
+                let id = id.parse().map_err(|_| format!("Invalid id {:?}", id))?;
+                if !self.remove_point(id) {
+                    return Err(format!("No breakpoint or watchpoint #{}", id).into());
+                }
+            }
+            ["points"] => {
+                for &(id, point) in &self.points {
+                    writeln!(out, "#{} {}", id, self.describe(point))?;
+                }
+            }
+            ["registers" | "r"] => self.write_registers(out)?,
+            ["set", register, value] => {
+                let register = self.register(register)?;
+                self.cpu.registers[register] = value
+                    .parse()
+                    .map_err(|_| format!("Invalid value {:?}", value))?;
+                self.write_registers(out)?;
+            }
+            ["list" | "l", ..] => self.write_instructions(out, count(1, 1)? as usize)?,
+            ["help" | "h"] => writeln!(out, "{}", HELP)?,
+            ["quit" | "q"] => return Ok(false),
+            _ => return Err(format!("Unknown command {:?}, try help", line.trim()).into()),
+        }
+        Ok(true)
+    }
+
+    /// Reads commands until the input ends or `quit`, writing a prompt before each.
+    pub fn repl(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        self.write_stop(&mut out, Stop::Done)?;
+        let mut previous = String::new();
+        write!(out, "(debug) ")?;
+        out.flush()?;
+        for line in input.lines() {
+            let mut line = line?;
+            if line.trim().is_empty() {
+                line = previous.clone();
+            }
+            match self.command(&line, &mut out) {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(e) => writeln!(out, "error: {}", e)?,
+            }
+            previous = line;
+            write!(out, "(debug) ")?;
+            out.flush()?;
+        }
+        // The input ended without a newline after the prompt.
+        writeln!(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Debugger, Stop};
+    use crate::cpu::get_cpu;
+    use crate::lines;
+
+    const PROGRAM: &str = lines!(
+        "#ip 0"
+        "seti 5 0 1"
+        "seti 6 0 2"
+        "addi 0 1 0"
+        "addr 1 2 3"
+        "setr 1 0 0"
+        "seti 8 0 4"
+        "seti 9 0 5"
+    );
+
+    fn session(commands: &str) -> String {
+        let mut debugger = Debugger::new(get_cpu(PROGRAM, [0; 6]).unwrap());
+        let mut out = Vec::new();
+        debugger.repl(commands.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn stops_on_breakpoints_and_watchpoints() {
+        assert_eq!(
+            session(lines!(
+                "break 4"
+                "watch r1"
+                "step"
+                "continue"
+                "delete 2"
+                "c"
+                ""
+            )),
+            lines!(
+                "ip=0 r1=0 r2=0 r3=0 r4=0 r5=0"
+                "=> 0: seti 5 0 1  r1 = 5"
+                "(debug) #1 break at 4"
+                "(debug) #2 watch r1"
+                "(debug) #2 watch r1, after 1 steps"
+                "ip=1 r1=5 r2=0 r3=0 r4=0 r5=0"
+                "=> 1: seti 6 0 2  r2 = 6"
+                "(debug) #1 break at 4, after 3 steps"
+                "ip=4 r1=5 r2=6 r3=0 r4=0 r5=0"
+                "=> 4: setr 1 0 0  ip = r1  ; goto r1 + 1"
+                "(debug) (debug) halted after 5 steps"
+                "ip=7 r1=5 r2=6 r3=0 r4=0 r5=9"
+                "(debug) halted after 5 steps"
+                "ip=7 r1=5 r2=6 r3=0 r4=0 r5=9"
+                "(debug) "
+            )
+        );
+    }
+
+    #[test]
+    fn changes_registers() {
+        assert_eq!(
+            session(lines!(
+                "break ip == 2"
+                "set ip 1"
+                "set r9 1"
+                "continue"
+                "list 2"
+                "quit"
+                "registers"
+            )),
+            concat!(
+                lines!(
+                "ip=0 r1=0 r2=0 r3=0 r4=0 r5=0"
+                "=> 0: seti 5 0 1  r1 = 5"
+                "(debug) #1 break when ip == 2"
+                "(debug) ip=1 r1=0 r2=0 r3=0 r4=0 r5=0"
+                "(debug) error: Unknown register \"r9\""
+                "(debug) #1 break when ip == 2, after 1 steps"
+                "ip=2 r1=0 r2=6 r3=0 r4=0 r5=0"
+                "=> 2: addi 0 1 0  ip = ip + 1  ; goto L4"
+                "(debug) => 2: addi 0 1 0  ip = ip + 1  ; goto L4"
+                "   3: addr 1 2 3  r3 = r1 + r2"
+                ),
+                // Quitting doesn't print anything after the prompt.
+                "(debug) "
+            )
+        );
+    }
+
+    #[test]
+    fn counts_steps() {
+        let mut debugger = Debugger::new(get_cpu(PROGRAM, [0; 6]).unwrap());
+        assert_eq!(debugger.run(2, true), Stop::Done);
+        assert_eq!(debugger.run(u64::MAX, true), Stop::Halted);
+        assert_eq!(debugger.steps(), 5);
+        assert_eq!(debugger.cpu().registers, [7, 5, 6, 0, 0, 9]);
+    }
+}
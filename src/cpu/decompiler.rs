@@ -0,0 +1,885 @@
+use super::cfg::{Cfg, Successor};
+use super::disassembler::{Disassembler, Jump, Operand};
+use super::{InstructionKind, CPU};
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Multiply,
+    And,
+    Or,
+    Greater,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        use self::Operator::*;
+        match self {
+            Add => "+",
+            Multiply => "*",
+            And => "&",
+            Or => "|",
+            Greater => ">",
+            LessOrEqual => "<=",
+            Equal => "==",
+            NotEqual => "!=",
+        }
+    }
+
+    /// Binding strength following Rust, comparisons bind the weakest.
+    fn precedence(self) -> u8 {
+        use self::Operator::*;
+        match self {
+            Multiply => 4,
+            Add => 3,
+            And => 2,
+            Or => 1,
+            Greater | LessOrEqual | Equal | NotEqual => 0,
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        self.precedence() == 0
+    }
+
+    fn is_commutative(self) -> bool {
+        !matches!(self, Operator::Greater | Operator::LessOrEqual)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expression {
+    Register(usize),
+    Constant(usize),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    fn binary(operator: Operator, a: Expression, b: Expression) -> Self {
+        Expression::Binary(operator, Box::new(a), Box::new(b))
+    }
+
+    /// Condition true when the expression is, comparisons are kept as they are.
+    fn condition(self) -> Self {
+        match self {
+            Expression::Binary(operator, ..) if operator.is_comparison() => self,
+            _ => Expression::binary(Operator::NotEqual, self, Expression::Constant(0)),
+        }
+    }
+
+    fn negate(self) -> Self {
+        use self::Operator::*;
+        match self {
+            Expression::Binary(operator, a, b) if operator.is_comparison() => {
+                let negated = match operator {
+                    Greater => LessOrEqual,
+                    LessOrEqual => Greater,
+                    Equal => NotEqual,
+                    _ => Equal,
+                };
+                Expression::Binary(negated, a, b)
+            }
+            _ => Expression::binary(Equal, self, Expression::Constant(0)),
+        }
+    }
+
+    fn uses(&self, register: usize) -> usize {
+        match self {
+            Expression::Register(r) => (*r == register) as usize,
+            Expression::Constant(_) => 0,
+            Expression::Binary(_, a, b) => a.uses(register) + b.uses(register),
+        }
+    }
+
+    fn registers(&self, registers: &mut u8) {
+        match self {
+            Expression::Register(r) => *registers |= 1 << r,
+            Expression::Constant(_) => {}
+            Expression::Binary(_, a, b) => {
+                a.registers(registers);
+                b.registers(registers);
+            }
+        }
+    }
+
+    fn substitute(&mut self, register: usize, replacement: &Expression) {
+        match self {
+            Expression::Register(r) if *r == register => *self = replacement.clone(),
+            Expression::Binary(_, a, b) => {
+                a.substitute(register, replacement);
+                b.substitute(register, replacement);
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&self, precedence: u8) -> String {
+        match self {
+            Expression::Register(r) => format!("r{}", r),
+            Expression::Constant(value) => value.to_string(),
+            Expression::Binary(operator, a, b) => {
+                let own = operator.precedence();
+                // Comparisons don't chain, so their operands are grouped on both sides.
+                let left = if operator.is_comparison() {
+                    own + 1
+                } else {
+                    own
+                };
+                let text = format!(
+                    "{} {} {}",
+                    a.render(left),
+                    operator.symbol(),
+                    b.render(own + 1)
+                );
+                if own < precedence {
+                    format!("({})", text)
+                } else {
+                    text
+                }
+            }
+        }
+    }
+}
+
+/// Structured pseudo-Rust statement. Loops are identified by the address of their header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Statement {
+    Assign(usize, Expression),
+    If(Expression, Vec<Statement>, Vec<Statement>),
+    Loop(usize, Vec<Statement>),
+    While(usize, Expression, Vec<Statement>),
+    /// Jump to a base address plus the value of an expression, with the blocks reached by
+    /// values from 0.
+    Match(Expression, usize, Vec<Vec<Statement>>),
+    /// Leaves a loop, the innermost one when `None`.
+    Break(Option<usize>),
+    Continue(Option<usize>),
+    Return,
+    Goto(usize),
+    /// Start of a block, only shown when jumped to by a `Goto`.
+    Label(usize),
+    /// Jump to one past the value of an expression.
+    Jump(Expression),
+}
+
+impl Statement {
+    fn is_jump(&self) -> bool {
+        use self::Statement::*;
+        matches!(self, Break(_) | Continue(_) | Return | Goto(_) | Jump(_))
+    }
+}
+
+enum Terminator {
+    Next(Successor),
+    Branch {
+        condition: Expression,
+        taken: Successor,
+        fallthrough: Successor,
+    },
+    Offset {
+        value: Expression,
+        base: usize,
+        targets: Vec<Successor>,
+    },
+    Computed(Expression),
+}
+
+impl Terminator {
+    fn expression(&mut self) -> Option<&mut Expression> {
+        match self {
+            Terminator::Next(_) => None,
+            Terminator::Branch { condition: e, .. }
+            | Terminator::Offset { value: e, .. }
+            | Terminator::Computed(e) => Some(e),
+        }
+    }
+}
+
+/// Block lifted to assignments, without the instruction pointer register.
+struct Lifted {
+    address: usize,
+    statements: Vec<(usize, Expression)>,
+    terminator: Terminator,
+}
+
+#[derive(Clone)]
+struct ActiveLoop {
+    header: usize,
+    address: usize,
+    follow: Option<usize>,
+    body: BTreeSet<usize>,
+}
+
+/// Recovers `if` and `while` structure from the jumps of an elf-code program.
+pub struct Decompiler {
+    cfg: Cfg,
+    blocks: Vec<Lifted>,
+    emitted: Vec<bool>,
+}
+
+impl Decompiler {
+    pub fn new(cpu: &CPU) -> Self {
+        let cfg = Cfg::new(cpu);
+        let disassembler = Disassembler::new(cpu);
+        let ip = cpu.ip_binding();
+        let instructions = cpu.instructions();
+        let mut blocks: Vec<_> = cfg
+            .blocks()
+            .iter()
+            .map(|block| {
+                let expression = |address: usize| {
+                    let instruction = &instructions[address];
+                    // The instruction pointer register holds the address while it runs.
+                    let operand = |operand| match operand {
+                        Operand::Register(r) if Some(r) == ip => Expression::Constant(address),
+                        Operand::Register(r) => Expression::Register(r),
+                        Operand::Immediate(value) => Expression::Constant(value),
+                    };
+                    let (a, b) = instruction.operands();
+                    match (operator(instruction.kind), b) {
+                        (Some(operator), Some(b)) => {
+                            Expression::binary(operator, operand(a), operand(b))
+                        }
+                        _ => operand(a),
+                    }
+                };
+                let last = block.addresses.end - 1;
+                let jump = disassembler.jump(last);
+                let end = if jump.is_some() {
+                    last
+                } else {
+                    block.addresses.end
+                };
+                let statements = (block.addresses.start..end)
+                    .map(|address| (instructions[address].destination(), expression(address)))
+                    .collect();
+                let successors = &block.successors;
+                let terminator = match jump {
+                    None | Some(Jump::Goto(_)) => Terminator::Next(successors[0]),
+                    Some(Jump::Branch { condition, .. }) => Terminator::Branch {
+                        condition: Expression::Register(condition),
+                        taken: successors.get(1).copied().unwrap_or(successors[0]),
+                        fallthrough: successors[0],
+                    },
+                    Some(Jump::Offset { register, base }) => Terminator::Offset {
+                        value: Expression::Register(register),
+                        base,
+                        targets: (base..base + 2)
+                            .map(|address| match cfg.block_at(address) {
+                                Some(b) => Successor::Block(b),
+                                None => Successor::Exit,
+                            })
+                            .collect(),
+                    },
+                    Some(Jump::Computed) => Terminator::Computed(expression(last)),
+                };
+                Lifted {
+                    address: block.addresses.start,
+                    statements,
+                    terminator,
+                }
+            })
+            .collect();
+        let live = live_out(&cfg, &blocks);
+        for (block, live) in blocks.iter_mut().zip(live) {
+            fold(block, live);
+        }
+        let emitted = vec![false; blocks.len()];
+        Decompiler {
+            cfg,
+            blocks,
+            emitted,
+        }
+    }
+
+    /// Structured statements of the whole program.
+    pub fn statements(mut self) -> Vec<Statement> {
+        if self.blocks.is_empty() {
+            return Vec::new();
+        }
+        let statements = self.sequence(Successor::Block(0), None, &[], None);
+        let mut targets = BTreeSet::new();
+        collect_gotos(&statements, &mut targets);
+        simplify(statements, &targets)
+    }
+
+    /// Writes the program as pseudo-Rust.
+    pub fn write(self, mut out: impl Write) -> io::Result<()> {
+        let statements = self.statements();
+        let mut labels = BTreeSet::new();
+        collect_loop_labels(&statements, &mut labels);
+        writeln!(out, "fn main() {{")?;
+        write_statements(&mut out, &statements, 1, &labels)?;
+        writeln!(out, "}}")
+    }
+
+    fn loop_at(&self, block: usize) -> Option<ActiveLoop> {
+        let l = self.cfg.loops().iter().find(|l| l.header == block)?;
+        let mut exits = l
+            .body
+            .iter()
+            .flat_map(|&b| self.cfg.blocks()[b].successors.iter())
+            .filter_map(|&s| match s {
+                Successor::Block(s) if !l.body.contains(&s) => Some(s),
+                _ => None,
+            });
+        Some(ActiveLoop {
+            header: block,
+            address: self.blocks[block].address,
+            follow: exits.next(),
+            body: l.body.clone(),
+        })
+    }
+
+    /// Where the paths from a branch join again, if that happens in the innermost loop.
+    fn merge(&self, block: usize, loops: &[ActiveLoop]) -> Option<usize> {
+        match self.cfg.immediate_post_dominator(block)? {
+            Successor::Block(merge) => match loops.last() {
+                Some(l) if !l.body.contains(&merge) => None,
+                _ => Some(merge),
+            },
+            _ => None,
+        }
+    }
+
+    fn sequence(
+        &mut self,
+        mut current: Successor,
+        stop: Option<usize>,
+        loops: &[ActiveLoop],
+        mut entering: Option<usize>,
+    ) -> Vec<Statement> {
+        let mut out = Vec::new();
+        loop {
+            let block = match current {
+                Successor::Block(block) => block,
+                Successor::Exit => {
+                    out.push(Statement::Return);
+                    break;
+                }
+                Successor::Unknown => break,
+            };
+            if Some(block) == stop {
+                break;
+            }
+            if entering != Some(block) {
+                let label = |depth: usize| {
+                    if depth + 1 == loops.len() {
+                        None
+                    } else {
+                        Some(loops[depth].address)
+                    }
+                };
+                if let Some(depth) = loops.iter().rposition(|l| l.header == block) {
+                    out.push(Statement::Continue(label(depth)));
+                    break;
+                }
+                if let Some(depth) = loops.iter().rposition(|l| l.follow == Some(block)) {
+                    out.push(Statement::Break(label(depth)));
+                    break;
+                }
+                if self.emitted[block] {
+                    out.push(Statement::Goto(self.blocks[block].address));
+                    break;
+                }
+                if let Some(l) = self.loop_at(block) {
+                    let follow = l.follow;
+                    let address = l.address;
+                    let mut inner = loops.to_vec();
+                    inner.push(l);
+                    let body = self.sequence(current, None, &inner, Some(block));
+                    out.push(Statement::Loop(address, body));
+                    match follow {
+                        Some(follow) => {
+                            current = Successor::Block(follow);
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+            }
+            entering = None;
+            self.emitted[block] = true;
+            let lifted = &self.blocks[block];
+            out.push(Statement::Label(lifted.address));
+            out.extend(
+                lifted
+                    .statements
+                    .iter()
+                    .map(|(r, e)| Statement::Assign(*r, e.clone())),
+            );
+            let merge = self.merge(block, loops);
+            let until = merge.or(stop);
+            match &self.blocks[block].terminator {
+                Terminator::Next(successor) => current = *successor,
+                Terminator::Branch {
+                    condition,
+                    taken,
+                    fallthrough,
+                } => {
+                    let condition = condition.clone().condition();
+                    let (taken, fallthrough) = (*taken, *fallthrough);
+                    let then = self.sequence(taken, until, loops, None);
+                    let otherwise = self.sequence(fallthrough, until, loops, None);
+                    out.push(Statement::If(condition, then, otherwise));
+                    match merge {
+                        Some(merge) => current = Successor::Block(merge),
+                        None => break,
+                    }
+                }
+                Terminator::Offset {
+                    value,
+                    base,
+                    targets,
+                } => {
+                    let (value, base, targets) = (value.clone(), *base, targets.clone());
+                    let arms = targets
+                        .into_iter()
+                        .map(|target| self.sequence(target, until, loops, None))
+                        .collect();
+                    out.push(Statement::Match(value, base, arms));
+                    match merge {
+                        Some(merge) => current = Successor::Block(merge),
+                        None => break,
+                    }
+                }
+                Terminator::Computed(expression) => {
+                    out.push(Statement::Jump(expression.clone()));
+                    break;
+                }
+            }
+        }
+        out
+    }
+}
+
+fn operator(kind: InstructionKind) -> Option<Operator> {
+    use self::InstructionKind::*;
+    match kind {
+        Addr | Addi => Some(Operator::Add),
+        Mulr | Muli => Some(Operator::Multiply),
+        Banr | Bani => Some(Operator::And),
+        Borr | Bori => Some(Operator::Or),
+        Setr | Seti => None,
+        Gtir | Gtri | Gtrr => Some(Operator::Greater),
+        Eqir | Eqri | Eqrr => Some(Operator::Equal),
+    }
+}
+
+/// Registers live at the end of each block, as bit sets. The answer is read from register 0 when
+/// the program halts, everything is live when it jumps somewhere unknown.
+fn live_out(cfg: &Cfg, blocks: &[Lifted]) -> Vec<u8> {
+    let (used, defined): (Vec<u8>, Vec<u8>) = blocks
+        .iter()
+        .map(|block| {
+            let (mut used, mut defined) = (0, 0);
+            let mut read = |e: &Expression, defined: u8| {
+                let mut registers = 0;
+                e.registers(&mut registers);
+                used |= registers & !defined;
+            };
+            for (r, e) in &block.statements {
+                read(e, defined);
+                defined |= 1 << r;
+            }
+            match &block.terminator {
+                Terminator::Next(_) => {}
+                Terminator::Branch { condition: e, .. }
+                | Terminator::Offset { value: e, .. }
+                | Terminator::Computed(e) => read(e, defined),
+            }
+            (used, defined)
+        })
+        .unzip();
+    let mut live_in = vec![0u8; blocks.len()];
+    let mut live_out = vec![0u8; blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in (0..blocks.len()).rev() {
+            let out = cfg.blocks()[block]
+                .successors
+                .iter()
+                .fold(0, |out, &s| match s {
+                    Successor::Block(s) => out | live_in[s],
+                    Successor::Exit => out | 1,
+                    Successor::Unknown => 0b11_1111,
+                });
+            let into = used[block] | (out & !defined[block]);
+            if out != live_out[block] || into != live_in[block] {
+                live_out[block] = out;
+                live_in[block] = into;
+                changed = true;
+            }
+        }
+    }
+    live_out
+}
+
+/// Substitutes assignments read only once into the expression reading them.
+fn fold(block: &mut Lifted, live_out: u8) {
+    'search: loop {
+        for i in 0..block.statements.len() {
+            let register = block.statements[i].0;
+            let mut operands = 0;
+            block.statements[i].1.registers(&mut operands);
+            let mut uses = 0;
+            let mut target = None;
+            let mut clobbered = false;
+            let mut redefined = false;
+            for j in i + 1..block.statements.len() {
+                let (destination, expression) = &block.statements[j];
+                let count = expression.uses(register);
+                if count > 0 {
+                    uses += count;
+                    if target.is_none() && !clobbered {
+                        target = Some(j);
+                    }
+                }
+                if *destination == register {
+                    redefined = true;
+                    break;
+                }
+                if operands & (1 << destination) != 0 {
+                    clobbered = true;
+                }
+            }
+            if !redefined {
+                if let Some(e) = block.terminator.expression() {
+                    let count = e.uses(register);
+                    if count > 0 {
+                        uses += count;
+                        if target.is_none() && !clobbered {
+                            target = Some(block.statements.len());
+                        }
+                    }
+                }
+                if live_out & (1 << register) != 0 {
+                    continue;
+                }
+            }
+            let target = match target {
+                Some(target) if uses == 1 => target,
+                _ => continue,
+            };
+            let (_, replacement) = block.statements.remove(i);
+            let expression = match block.statements.get_mut(target - 1) {
+                Some((_, expression)) => expression,
+                None => block.terminator.expression().unwrap(),
+            };
+            expression.substitute(register, &replacement);
+            continue 'search;
+        }
+        break;
+    }
+}
+
+fn collect_gotos(statements: &[Statement], targets: &mut BTreeSet<usize>) {
+    for statement in statements {
+        match statement {
+            Statement::Goto(address) => {
+                targets.insert(*address);
+            }
+            Statement::If(_, a, b) => {
+                collect_gotos(a, targets);
+                collect_gotos(b, targets);
+            }
+            Statement::Loop(_, body) | Statement::While(_, _, body) => collect_gotos(body, targets),
+            Statement::Match(_, _, arms) => arms.iter().for_each(|a| collect_gotos(a, targets)),
+            _ => {}
+        }
+    }
+}
+
+fn collect_loop_labels(statements: &[Statement], labels: &mut BTreeSet<usize>) {
+    for statement in statements {
+        match statement {
+            Statement::Break(Some(address)) | Statement::Continue(Some(address)) => {
+                labels.insert(*address);
+            }
+            Statement::If(_, a, b) => {
+                collect_loop_labels(a, labels);
+                collect_loop_labels(b, labels);
+            }
+            Statement::Loop(_, body) | Statement::While(_, _, body) => {
+                collect_loop_labels(body, labels)
+            }
+            Statement::Match(_, _, arms) => {
+                arms.iter().for_each(|a| collect_loop_labels(a, labels))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Tidies up the structure: drops unused labels and empty branches, turns loops testing
+/// their exit first into `while`.
+fn simplify(statements: Vec<Statement>, targets: &BTreeSet<usize>) -> Vec<Statement> {
+    let mut out = Vec::new();
+    for statement in statements {
+        match statement {
+            Statement::Label(address) if !targets.contains(&address) => {}
+            Statement::If(condition, then, otherwise) => {
+                let then = simplify(then, targets);
+                let otherwise = simplify(otherwise, targets);
+                match (then.is_empty(), otherwise.is_empty()) {
+                    (true, true) => {}
+                    (true, false) => out.push(Statement::If(condition.negate(), otherwise, then)),
+                    _ if then.last().is_some_and(Statement::is_jump) => {
+                        out.push(Statement::If(condition, then, Vec::new()));
+                        out.extend(otherwise);
+                    }
+                    _ => out.push(Statement::If(condition, then, otherwise)),
+                }
+            }
+            Statement::Loop(address, body) => {
+                let mut body = simplify(body, targets);
+                strip_continue(&mut body);
+                let body = simplify(body, targets);
+                match body.first() {
+                    Some(Statement::If(_, then, otherwise))
+                        if then == &[Statement::Break(None)] && otherwise.is_empty() =>
+                    {
+                        let mut body = body.into_iter();
+                        if let Some(Statement::If(condition, ..)) = body.next() {
+                            out.push(Statement::While(
+                                address,
+                                condition.negate(),
+                                body.collect(),
+                            ));
+                        }
+                    }
+                    _ => out.push(Statement::Loop(address, body)),
+                }
+            }
+            Statement::Match(value, base, arms) => {
+                let arms = arms.into_iter().map(|a| simplify(a, targets)).collect();
+                out.push(Statement::Match(value, base, arms));
+            }
+            statement => out.push(statement),
+        }
+    }
+    out
+}
+
+/// Removes `continue` at the end of a loop body, where it does nothing.
+fn strip_continue(body: &mut Vec<Statement>) {
+    match body.last_mut() {
+        Some(Statement::Continue(None)) => {
+            body.pop();
+        }
+        Some(Statement::If(_, then, otherwise)) => {
+            strip_continue(then);
+            strip_continue(otherwise);
+        }
+        _ => {}
+    }
+}
+
+fn write_statements(
+    out: &mut impl Write,
+    statements: &[Statement],
+    depth: usize,
+    labels: &BTreeSet<usize>,
+) -> io::Result<()> {
+    let indent = "    ".repeat(depth);
+    let label = |address: &usize| {
+        if labels.contains(address) {
+            format!("'l{}: ", address)
+        } else {
+            String::new()
+        }
+    };
+    let target = |address: &Option<usize>| match address {
+        Some(address) => format!(" 'l{}", address),
+        None => String::new(),
+    };
+    for statement in statements {
+        match statement {
+            Statement::Assign(register, expression) => {
+                writeln!(out, "{}{};", indent, assignment(*register, expression))?
+            }
+            Statement::If(condition, then, otherwise) => {
+                writeln!(out, "{}if {} {{", indent, condition.render(0))?;
+                write_statements(out, then, depth + 1, labels)?;
+                if !otherwise.is_empty() {
+                    writeln!(out, "{}}} else {{", indent)?;
+                    write_statements(out, otherwise, depth + 1, labels)?;
+                }
+                writeln!(out, "{}}}", indent)?;
+            }
+            Statement::Loop(address, body) => {
+                writeln!(out, "{}{}loop {{", indent, label(address))?;
+                write_statements(out, body, depth + 1, labels)?;
+                writeln!(out, "{}}}", indent)?;
+            }
+            Statement::While(address, condition, body) => {
+                let condition = condition.render(0);
+                writeln!(out, "{}{}while {} {{", indent, label(address), condition)?;
+                write_statements(out, body, depth + 1, labels)?;
+                writeln!(out, "{}}}", indent)?;
+            }
+            Statement::Match(value, base, arms) => {
+                writeln!(out, "{}match {} {{", indent, value.render(0))?;
+                for (i, arm) in arms.iter().enumerate() {
+                    if arm.is_empty() {
+                        writeln!(out, "{}    {} => {{}}", indent, i)?;
+                    } else {
+                        writeln!(out, "{}    {} => {{", indent, i)?;
+                        write_statements(out, arm, depth + 2, labels)?;
+                        writeln!(out, "{}    }}", indent)?;
+                    }
+                }
+                writeln!(
+                    out,
+                    "{}    _ => goto {} + {},",
+                    indent,
+                    base,
+                    value.render(3)
+                )?;
+                writeln!(out, "{}}}", indent)?;
+            }
+            Statement::Break(address) => writeln!(out, "{}break{};", indent, target(address))?,
+            Statement::Continue(address) => {
+                writeln!(out, "{}continue{};", indent, target(address))?
+            }
+            Statement::Return => writeln!(out, "{}return;", indent)?,
+            Statement::Goto(address) => writeln!(out, "{}goto L{};", indent, address)?,
+            Statement::Label(address) => {
+                writeln!(out, "{}L{}:", "    ".repeat(depth - 1), address)?
+            }
+            Statement::Jump(expression) => {
+                writeln!(out, "{}goto {} + 1;", indent, expression.render(3))?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Assignment, using compound operators when the destination is an operand.
+fn assignment(register: usize, expression: &Expression) -> String {
+    if let Expression::Binary(operator, a, b) = expression {
+        if !operator.is_comparison() {
+            let own = Expression::Register(register);
+            let other = if **a == own {
+                Some(b)
+            } else if **b == own && operator.is_commutative() {
+                Some(a)
+            } else {
+                None
+            };
+            if let Some(other) = other {
+                return format!("r{} {}= {}", register, operator.symbol(), other.render(0));
+            }
+        }
+    }
+    format!("r{} = {}", register, expression.render(0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::Decompiler;
+    use crate::cpu::get_cpu;
+    use crate::lines;
+
+    fn decompile(program: &str) -> String {
+        let cpu = get_cpu(program, [0; 6]).unwrap();
+        let mut out = Vec::new();
+        Decompiler::new(&cpu).write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn structures_nested_loops() {
+        let program = lines!(
+            "#ip 4"
+            "seti 1 0 1"
+            "seti 1 0 5"
+            "mulr 1 5 3"
+            "eqrr 3 2 3"
+            "addr 3 4 4"
+            "addi 4 1 4"
+            "addr 1 0 0"
+            "addi 5 1 5"
+            "gtrr 5 2 3"
+            "addr 4 3 4"
+            "seti 1 0 4"
+            "addi 1 1 1"
+            "gtrr 1 2 3"
+            "addr 3 4 4"
+            "seti 0 0 4"
+            "mulr 4 4 4"
+        );
+        assert_eq!(
+            decompile(program),
+            lines!(
+                "fn main() {"
+                "    r1 = 1;"
+                "    loop {"
+                "        r5 = 1;"
+                "        loop {"
+                "            if r1 * r5 == r2 {"
+                "                r0 += r1;"
+                "            }"
+                "            r5 += 1;"
+                "            if r5 > r2 {"
+                "                break;"
+                "            }"
+                "        }"
+                "        r1 += 1;"
+                "        if r1 > r2 {"
+                "            break;"
+                "        }"
+                "    }"
+                "    return;"
+                "}"
+            )
+        );
+    }
+
+    #[test]
+    fn turns_loops_testing_first_into_while() {
+        let program = lines!(
+            "#ip 4"
+            "seti 0 0 3"
+            "addi 3 1 1"
+            "muli 1 256 1"
+            "gtrr 1 5 1"
+            "addr 1 4 4"
+            "addi 4 1 4"
+            "seti 8 0 4"
+            "addi 3 1 3"
+            "seti 0 0 4"
+            "setr 3 0 0"
+        );
+        assert_eq!(
+            decompile(program),
+            lines!(
+                "fn main() {"
+                "    r3 = 0;"
+                "    while (r3 + 1) * 256 <= r5 {"
+                "        r3 += 1;"
+                "    }"
+                "    r0 = r3;"
+                "    return;"
+                "}"
+            )
+        );
+    }
+
+    #[test]
+    fn decompiles_bundled_programs() {
+        let day19 = decompile(include_str!("../day19/input"));
+        assert!(day19.contains("            if r1 * r5 == r2 {\n                r0 += r1;\n"));
+        assert!(!day19.contains("goto L"));
+        let day21 = decompile(include_str!("../day21/input"));
+        assert!(day21.contains("while (r3 + 1) * 256 <= r5 {\n"));
+        assert!(day21.contains("        if r2 == r0 {\n            return;\n"));
+        assert!(!day21.contains("goto L"));
+    }
+}
@@ -0,0 +1,385 @@
+use super::{Arithmetic, Instruction, InstructionKind, CPU};
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+
+/// Place control is transferred to by a jump.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    Address(usize),
+    /// Outside of the program, which stops it.
+    Halt,
+}
+
+/// Effect of an instruction writing the instruction pointer register.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Jump {
+    /// Jump to a destination known without running the program.
+    Goto(Target),
+    /// Skip the next instruction when a register set by a comparison right before is 1.
+    Branch { condition: usize, taken: Target },
+    /// Jump to `base` plus the value of a register.
+    Offset { register: usize, base: usize },
+    /// Jump to a destination computed from registers in another way.
+    Computed,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Register(usize),
+    Immediate(usize),
+}
+
+impl InstructionKind {
+    fn operator(self) -> Option<&'static str> {
+        use self::InstructionKind::*;
+        match self {
+            Addr | Addi => Some("+"),
+            Mulr | Muli => Some("*"),
+            Banr | Bani => Some("&"),
+            Borr | Bori => Some("|"),
+            Setr | Seti => None,
+            Gtir | Gtri | Gtrr => Some(">"),
+            Eqir | Eqri | Eqrr => Some("=="),
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        matches!(self.operator(), Some(">") | Some("=="))
+    }
+}
+
+impl Instruction {
+    /// Operands read by the instruction, the second one is missing for assignments.
+    pub fn operands(&self) -> (Operand, Option<Operand>) {
+        use self::InstructionKind::*;
+        use self::Operand::{Immediate, Register};
+        let [a, b, _] = self.parameters;
+        match self.kind {
+            Addr | Mulr | Banr | Borr | Gtrr | Eqrr => (Register(a), Some(Register(b))),
+            Addi | Muli | Bani | Bori | Gtri | Eqri => (Register(a), Some(Immediate(b))),
+            Gtir | Eqir => (Immediate(a), Some(Register(b))),
+            Setr => (Register(a), None),
+            Seti => (Immediate(a), None),
+        }
+    }
+
+    /// Register written by the instruction.
+    pub fn destination(&self) -> usize {
+        self.parameters[2]
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.parameters;
+        let kind = format!("{:?}", self.kind).to_lowercase();
+        write!(f, "{} {} {} {}", kind, a, b, c)
+    }
+}
+
+/// Translates elf-code into pseudo-code, resolving jumps through the instruction pointer.
+pub struct Disassembler<'a> {
+    instructions: &'a [Instruction],
+    ip: Option<usize>,
+    arithmetic: Arithmetic,
+    names: [String; 6],
+}
+
+impl<'a> Disassembler<'a> {
+    /// Registers are named `r0` to `r5`, except for the one bound to the instruction pointer,
+    /// which is called `ip`.
+    pub fn new(cpu: &'a CPU) -> Self {
+        let ip = cpu.ip_binding();
+        let mut names: [String; 6] = Default::default();
+        for (i, name) in names.iter_mut().enumerate() {
+            *name = format!("r{}", i);
+        }
+        if let Some(ip) = ip {
+            names[ip] = "ip".into();
+        }
+        Disassembler {
+            instructions: cpu.instructions(),
+            ip,
+            arithmetic: cpu.arithmetic(),
+            names,
+        }
+    }
+
+    pub fn rename(&mut self, register: usize, name: impl Into<String>) {
+        self.names[register] = name.into();
+    }
+
+    pub fn name(&self, register: usize) -> &str {
+        &self.names[register]
+    }
+
+    fn operand(&self, operand: Operand) -> String {
+        match operand {
+            Operand::Register(register) => self.name(register).into(),
+            Operand::Immediate(value) => value.to_string(),
+        }
+    }
+
+    /// Right hand side of the assignment done by an instruction.
+    pub fn expression(&self, instruction: &Instruction) -> String {
+        let (a, b) = instruction.operands();
+        match (instruction.kind.operator(), b) {
+            (Some(operator), Some(b)) => {
+                format!("{} {} {}", self.operand(a), operator, self.operand(b))
+            }
+            _ => self.operand(a),
+        }
+    }
+
+    /// Pseudo-code of the instruction at an address.
+    pub fn statement(&self, address: usize) -> Option<String> {
+        let instruction = self.instructions.get(address)?;
+        Some(format!(
+            "{} = {}",
+            self.name(instruction.destination()),
+            self.expression(instruction)
+        ))
+    }
+
+    fn target(&self, value: usize) -> Target {
+        // The instruction pointer is incremented after the jump.
+        match value.checked_add(1) {
+            Some(address) if address < self.instructions.len() => Target::Address(address),
+            _ => Target::Halt,
+        }
+    }
+
+    /// Resolves the jump done by the instruction at an address, `None` if it doesn't write the
+    /// instruction pointer, which is never the case when it isn't bound to a register.
+    pub fn jump(&self, address: usize) -> Option<Jump> {
+        let ip = self.ip?;
+        let instruction = self.instructions.get(address)?;
+        if instruction.destination() != ip {
+            return None;
+        }
+        // The instruction pointer register holds the address while the instruction runs.
+        let value = |operand| match operand {
+            Operand::Register(register) if register == ip => Some(address),
+            Operand::Register(_) => None,
+            Operand::Immediate(value) => Some(value),
+        };
+        let (a, b) = instruction.operands();
+        let mut registers = [0; 6];
+        registers[ip] = address;
+        match (value(a), b.map(value)) {
+            (Some(_), None) | (Some(_), Some(Some(_))) => {
+                // Overflowing stops the program.
+                let target = match instruction.run(registers, self.arithmetic) {
+                    Some(value) => self.target(value),
+                    None => Target::Halt,
+                };
+                return Some(Jump::Goto(target));
+            }
+            _ => {}
+        }
+        if let (InstructionKind::Addr, Operand::Register(x), Some(Operand::Register(y))) =
+            (instruction.kind, a, b)
+        {
+            let register = if x == ip { y } else { x };
+            if x == ip || y == ip {
+                let compared = address
+                    .checked_sub(1)
+                    .and_then(|previous| self.instructions.get(previous))
+                    .filter(|i| i.kind.is_comparison() && i.destination() == register);
+                return Some(match compared {
+                    Some(_) => Jump::Branch {
+                        condition: register,
+                        taken: self.target(address + 1),
+                    },
+                    None => Jump::Offset {
+                        register,
+                        base: address + 1,
+                    },
+                });
+            }
+        }
+        Some(Jump::Computed)
+    }
+
+    /// Addresses jumped to, which get labels.
+    pub fn labels(&self) -> BTreeSet<usize> {
+        (0..self.instructions.len())
+            .filter_map(|address| match self.jump(address)? {
+                Jump::Goto(Target::Address(target))
+                | Jump::Branch {
+                    taken: Target::Address(target),
+                    ..
+                } => Some(target),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn describe_target(target: Target) -> String {
+        match target {
+            Target::Address(address) => format!("goto L{}", address),
+            Target::Halt => "halt".into(),
+        }
+    }
+
+    /// Describes where the instruction at an address jumps to.
+    pub fn describe_jump(&self, address: usize) -> Option<String> {
+        Some(match self.jump(address)? {
+            Jump::Goto(target) => Self::describe_target(target),
+            Jump::Branch { condition, taken } => {
+                format!(
+                    "if {} {}",
+                    self.name(condition),
+                    Self::describe_target(taken)
+                )
+            }
+            Jump::Offset { register, base } => format!("goto {} + {}", base, self.name(register)),
+            Jump::Computed => format!("goto {} + 1", self.expression(&self.instructions[address])),
+        })
+    }
+
+    /// Writes the whole program, one instruction per line.
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        if let Some(ip) = self.ip {
+            writeln!(out, "#ip {} ({})", ip, self.name(ip))?;
+        }
+        let labels = self.labels();
+        let lines: Vec<_> = (0..self.instructions.len())
+            .map(|address| {
+                let label = if labels.contains(&address) {
+                    format!("L{}:", address)
+                } else {
+                    String::new()
+                };
+                (
+                    label,
+                    self.instructions[address].to_string(),
+                    self.statement(address).unwrap_or_default(),
+                    self.describe_jump(address),
+                )
+            })
+            .collect();
+        let label_width = lines.iter().map(|l| l.0.len()).max().unwrap_or(0);
+        let address_width = self.instructions.len().saturating_sub(1).to_string().len();
+        let raw_width = lines.iter().map(|l| l.1.len()).max().unwrap_or(0);
+        let statement_width = lines.iter().map(|l| l.2.len()).max().unwrap_or(0);
+        for (address, (label, raw, statement, jump)) in lines.iter().enumerate() {
+            let line = format!(
+                "{:lw$} {:>aw$}  {:rw$}  {:sw$}",
+                label,
+                address,
+                raw,
+                statement,
+                lw = label_width,
+                aw = address_width,
+                rw = raw_width,
+                sw = statement_width,
+            );
+            match jump {
+                Some(jump) => writeln!(out, "{}  ; {}", line, jump)?,
+                None => writeln!(out, "{}", line.trim_end())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Disassembler, Jump, Target};
+    use crate::cpu::{get_cpu, load_numeric, InstructionKind};
+    use crate::lines;
+
+    #[test]
+    fn resolves_jumps() {
+        let cpu = get_cpu(
+            lines!(
+                "#ip 2"
+                "seti 5 0 2"
+                "addi 2 1 2"
+                "eqrr 1 3 4"
+                "addr 4 2 2"
+                "addr 2 5 2"
+                "setr 1 0 2"
+                "mulr 2 2 2"
+            ),
+            [0; 6],
+        )
+        .unwrap();
+        let disassembler = Disassembler::new(&cpu);
+        let jumps: Vec<_> = (0..7).map(|a| disassembler.jump(a)).collect();
+        assert_eq!(
+            jumps,
+            [
+                Some(Jump::Goto(Target::Address(6))),
+                Some(Jump::Goto(Target::Address(3))),
+                None,
+                Some(Jump::Branch {
+                    condition: 4,
+                    taken: Target::Address(5),
+                }),
+                Some(Jump::Offset {
+                    register: 5,
+                    base: 5,
+                }),
+                Some(Jump::Computed),
+                Some(Jump::Goto(Target::Halt)),
+            ]
+        );
+        assert_eq!(
+            disassembler.labels().into_iter().collect::<Vec<_>>(),
+            [3, 5, 6]
+        );
+    }
+
+    #[test]
+    fn writes_pseudo_code() {
+        let cpu = get_cpu(
+            lines!(
+                "#ip 3"
+                "addi 3 2 3"
+                "seti 1 0 1"
+                "gtir 7 1 4"
+                "addr 3 4 3"
+                "seti 0 0 3"
+                "mulr 3 3 3"
+            ),
+            [0; 6],
+        )
+        .unwrap();
+        let mut disassembler = Disassembler::new(&cpu);
+        disassembler.rename(1, "n");
+        let mut out = Vec::new();
+        disassembler.write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            lines!(
+                "#ip 3 (ip)"
+                "    0  addi 3 2 3  ip = ip + 2   ; goto L3"
+                "L1: 1  seti 1 0 1  n = 1"
+                "    2  gtir 7 1 4  r4 = 7 > n"
+                "L3: 3  addr 3 4 3  ip = ip + r4  ; if r4 goto L5"
+                "    4  seti 0 0 3  ip = 0        ; goto L1"
+                "L5: 5  mulr 3 3 3  ip = ip * ip  ; halt"
+            )
+        );
+    }
+
+    #[test]
+    fn writes_programs_without_bound_ip() {
+        let opcodes = vec![(0, InstructionKind::Seti), (1, InstructionKind::Addr)]
+            .into_iter()
+            .collect();
+        let cpu = load_numeric(&[[0, 3, 0, 1], [1, 1, 1, 0]], &opcodes, [0; 6]).unwrap();
+        let mut out = Vec::new();
+        Disassembler::new(&cpu).write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            lines!(
+                " 0  seti 3 0 1  r1 = 3"
+                " 1  addr 1 1 0  r0 = r1 + r1"
+            )
+        );
+    }
+}
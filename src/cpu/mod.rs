@@ -0,0 +1,546 @@
+use self::disassembler::Operand;
+use crate::error;
+use nom::types::CompleteStr;
+use nom::{alt, do_parse, many0, map_res, named, tag, take_while};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+pub mod assembler;
+pub mod cfg;
+pub mod cycle;
+pub mod debugger;
+pub mod decompiler;
+pub mod disassembler;
+pub mod optimizer;
+pub mod symbolic;
+pub mod threaded;
+pub mod tracer;
+
+/// Way of running a program, one instruction at a time.
+pub trait Backend {
+    /// Runs the current instruction, `false` if the program halted instead.
+    fn step(&mut self) -> bool;
+
+    fn registers(&self) -> &[usize; 6];
+
+    fn registers_mut(&mut self) -> &mut [usize; 6];
+
+    /// Address of the current instruction.
+    fn address(&self) -> usize;
+
+    /// Register bound to the instruction pointer, if any.
+    fn ip_binding(&self) -> Option<usize>;
+
+    /// Instruction which overflowed, stopping the program.
+    fn overflow(&self) -> Option<Overflow>;
+
+    /// Runs until the program halts, returning register 0.
+    fn run(&mut self) -> Result<usize, Overflow> {
+        while self.step() {}
+        match self.overflow() {
+            Some(overflow) => Err(overflow),
+            None => Ok(self.registers()[0]),
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            address: self.address(),
+            registers: *self.registers(),
+        }
+    }
+}
+
+/// State of a machine, from which it runs the same way every time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Snapshot<const N: usize = 6> {
+    pub address: usize,
+    pub registers: [usize; N],
+}
+
+/// How additions and multiplications, including incrementing the instruction pointer, handle
+/// results which don't fit in a register.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Arithmetic {
+    /// Overflowing stops the program with an error.
+    #[default]
+    Checked,
+    /// Results wrap around at the size of `usize`.
+    Wrapping,
+    /// Registers are this many bits wide and every result wraps around.
+    Bits24,
+    Bits32,
+    Bits64,
+}
+
+impl Arithmetic {
+    fn bits(self) -> Option<u32> {
+        match self {
+            Arithmetic::Checked | Arithmetic::Wrapping => None,
+            Arithmetic::Bits24 => Some(24),
+            Arithmetic::Bits32 => Some(32),
+            Arithmetic::Bits64 => Some(64),
+        }
+    }
+
+    /// Largest value a register holds.
+    pub fn max(self) -> usize {
+        match self.bits() {
+            Some(bits) if bits < usize::BITS => (1 << bits) - 1,
+            _ => usize::MAX,
+        }
+    }
+
+    pub fn truncate(self, value: usize) -> usize {
+        value & self.max()
+    }
+
+    pub fn add(self, x: usize, y: usize) -> Option<usize> {
+        match self {
+            Arithmetic::Checked => x.checked_add(y),
+            _ => Some(self.truncate(x.wrapping_add(y))),
+        }
+    }
+
+    pub fn multiply(self, x: usize, y: usize) -> Option<usize> {
+        match self {
+            Arithmetic::Checked => x.checked_mul(y),
+            _ => Some(self.truncate(x.wrapping_mul(y))),
+        }
+    }
+}
+
+/// Instruction whose result doesn't fit in a register with checked arithmetic.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub address: usize,
+    pub instruction: Instruction,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Overflow at ip {} running {}",
+            self.address, self.instruction
+        )
+    }
+}
+
+impl Error for Overflow {}
+
+/// Error of tools which need the instruction pointer bound to a register, for programs loaded
+/// with numeric opcodes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnboundIp;
+
+impl Display for UnboundIp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "The instruction pointer isn't bound to a register")
+    }
+}
+
+impl Error for UnboundIp {}
+
+/// Interpreter for the elf-code used in days 16, 19 and 21, with `N` registers.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug)]
+pub struct CPU<const N: usize = 6> {
+    ip: Option<usize>,
+    /// Instruction pointer of programs which don't bind it to a register.
+    pointer: usize,
+    pub registers: [usize; N],
+    instructions: Vec<Instruction>,
+    arithmetic: Arithmetic,
+    overflow: Option<Overflow>,
+}
+
+impl<const N: usize> CPU<N> {
+    pub fn with_arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    /// Runs the current instruction, `false` if the program halted or overflowed instead. The
+    /// registers are left untouched by an instruction which overflows.
+    pub fn step(&mut self) -> bool {
+        if self.overflow.is_some() {
+            return false;
+        }
+        let instruction = match self.current_instruction() {
+            Some(instruction) => instruction,
+            None => return false,
+        };
+        let mut registers = self.registers;
+        let result = instruction
+            .run(registers, self.arithmetic)
+            .and_then(|value| {
+                registers[instruction.parameters[2]] = value;
+                match self.ip {
+                    Some(ip) => registers[ip] = self.arithmetic.add(registers[ip], 1)?,
+                    None => self.pointer += 1,
+                }
+                Some(())
+            });
+        match result {
+            Some(()) => {
+                self.registers = registers;
+                true
+            }
+            None => {
+                self.overflow = Some(Overflow {
+                    address: self.address(),
+                    instruction,
+                });
+                false
+            }
+        }
+    }
+
+    /// Instruction which overflowed, after which the program doesn't run anymore.
+    pub fn overflow(&self) -> Option<Overflow> {
+        self.overflow
+    }
+
+    /// Address of the current instruction.
+    pub fn address(&self) -> usize {
+        match self.ip {
+            Some(ip) => self.registers[ip],
+            None => self.pointer,
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot<N> {
+        Snapshot {
+            address: self.address(),
+            registers: self.registers,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot<N>) {
+        self.overflow = None;
+        self.registers = snapshot.registers;
+        self.pointer = snapshot.address;
+        if let Some(ip) = self.ip {
+            self.registers[ip] = snapshot.address;
+        }
+    }
+
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        self.instructions.get(self.address()).cloned()
+    }
+
+    /// Register bound to the instruction pointer with `#ip` directive, if any.
+    pub fn ip_binding(&self) -> Option<usize> {
+        self.ip
+    }
+
+    /// Register bound to the instruction pointer, for tools which can't run without one.
+    pub fn ip_register(&self) -> Result<usize, UnboundIp> {
+        self.ip.ok_or(UnboundIp)
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+}
+
+impl Backend for CPU {
+    fn step(&mut self) -> bool {
+        CPU::step(self)
+    }
+
+    fn registers(&self) -> &[usize; 6] {
+        &self.registers
+    }
+
+    fn registers_mut(&mut self) -> &mut [usize; 6] {
+        &mut self.registers
+    }
+
+    fn address(&self) -> usize {
+        CPU::address(self)
+    }
+
+    fn ip_binding(&self) -> Option<usize> {
+        self.ip
+    }
+
+    fn overflow(&self) -> Option<Overflow> {
+        self.overflow
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub kind: InstructionKind,
+    pub parameters: [usize; 3],
+}
+
+impl Instruction {
+    /// Value written to the output register, `None` if it overflows.
+    pub fn run<const N: usize>(
+        &self,
+        registers: [usize; N],
+        arithmetic: Arithmetic,
+    ) -> Option<usize> {
+        let [a, b, _] = self.parameters;
+        self.kind.apply(&registers, a, b, arithmetic)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InstructionKind {
+    Addr,
+    Addi,
+    Mulr,
+    Muli,
+    Banr,
+    Bani,
+    Borr,
+    Bori,
+    Setr,
+    Seti,
+    Gtir,
+    Gtri,
+    Gtrr,
+    Eqir,
+    Eqri,
+    Eqrr,
+}
+
+impl InstructionKind {
+    pub const ALL: [InstructionKind; 16] = {
+        use self::InstructionKind::*;
+        [
+            Addr, Addi, Mulr, Muli, Banr, Bani, Borr, Bori, Setr, Seti, Gtir, Gtri, Gtrr, Eqir,
+            Eqri, Eqrr,
+        ]
+    };
+
+    /// Value written to the output register, whatever the size of the register file. `None`
+    /// if it overflows.
+    pub fn apply(
+        self,
+        registers: &[usize],
+        a: usize,
+        b: usize,
+        arithmetic: Arithmetic,
+    ) -> Option<usize> {
+        use self::InstructionKind::*;
+        let value = match self {
+            Addr => return arithmetic.add(registers[a], registers[b]),
+            Addi => return arithmetic.add(registers[a], b),
+            Mulr => return arithmetic.multiply(registers[a], registers[b]),
+            Muli => return arithmetic.multiply(registers[a], b),
+            Banr => registers[a] & registers[b],
+            Bani => registers[a] & b,
+            Borr => registers[a] | registers[b],
+            Bori => registers[a] | b,
+            Setr => registers[a],
+            Seti => a,
+            Gtir => (a > registers[b]).into(),
+            Gtri => (registers[a] > b).into(),
+            Gtrr => (registers[a] > registers[b]).into(),
+            Eqir => (a == registers[b]).into(),
+            Eqri => (registers[a] == b).into(),
+            Eqrr => (registers[a] == registers[b]).into(),
+        };
+        Some(arithmetic.truncate(value))
+    }
+}
+
+/// Parses a program, starting it with given register values.
+pub fn get_cpu(input: &str, registers: [usize; 6]) -> Result<CPU, error::Error> {
+    let cpu = error::parse(input, input, cpu)?;
+    Ok(CPU { registers, ..cpu })
+}
+
+/// Loads a program of `[opcode, a, b, c]` lines using a mapping from numeric opcodes to
+/// instructions. Such programs don't bind the instruction pointer to a register.
+pub fn load_numeric<const N: usize>(
+    program: &[[usize; 4]],
+    opcodes: &HashMap<usize, InstructionKind>,
+    registers: [usize; N],
+) -> Result<CPU<N>, Box<dyn Error>> {
+    let instructions = program
+        .iter()
+        .map(|&[opcode, a, b, c]| {
+            let kind = *opcodes
+                .get(&opcode)
+                .ok_or_else(|| format!("Unknown opcode {}", opcode))?;
+            let instruction = Instruction {
+                kind,
+                parameters: [a, b, c],
+            };
+            let (first, second) = instruction.operands();
+            let out_of_range = [Some(first), second]
+                .iter()
+                .flatten()
+                .filter_map(|&operand| match operand {
+                    Operand::Register(register) => Some(register),
+                    Operand::Immediate(_) => None,
+                })
+                .chain(Some(c))
+                .find(|&register| register >= N);
+            match out_of_range {
+                Some(register) => {
+                    Err(format!("Register {} doesn't exist with {} registers", register, N).into())
+                }
+                None => Ok(instruction),
+            }
+        })
+        .collect::<Result<_, Box<dyn Error>>>()?;
+    Ok(CPU {
+        ip: None,
+        pointer: 0,
+        registers,
+        instructions,
+        arithmetic: Arithmetic::default(),
+        overflow: None,
+    })
+}
+
+named!(
+    cpu(CompleteStr<'_>) -> CPU,
+    do_parse!(
+        tag!("#ip ")
+            >> ip: integer
+            >> tag!("\n")
+            >> instructions: many0!(instruction)
+            >> (CPU {
+                ip: Some(ip),
+                pointer: 0,
+                instructions,
+                registers: [0; 6],
+                arithmetic: Arithmetic::default(),
+                overflow: None
+            })
+    )
+);
+
+named!(
+    instruction(CompleteStr<'_>) -> Instruction,
+    do_parse!(
+        kind: instruction_kind
+            >> tag!(" ")
+            >> first: integer
+            >> tag!(" ")
+            >> second: integer
+            >> tag!(" ")
+            >> third: integer
+            >> tag!("\n")
+            >> (Instruction {
+                kind,
+                parameters: [first, second, third]
+            })
+    )
+);
+
+named!(
+    instruction_kind(CompleteStr<'_>) -> InstructionKind,
+    alt!(
+        tag!("addr") => { |_| InstructionKind::Addr } |
+        tag!("addi") => { |_| InstructionKind::Addi } |
+        tag!("mulr") => { |_| InstructionKind::Mulr } |
+        tag!("muli") => { |_| InstructionKind::Muli } |
+        tag!("banr") => { |_| InstructionKind::Banr } |
+        tag!("bani") => { |_| InstructionKind::Bani } |
+        tag!("borr") => { |_| InstructionKind::Borr } |
+        tag!("bori") => { |_| InstructionKind::Bori } |
+        tag!("setr") => { |_| InstructionKind::Setr } |
+        tag!("seti") => { |_| InstructionKind::Seti } |
+        tag!("gtir") => { |_| InstructionKind::Gtir } |
+        tag!("gtri") => { |_| InstructionKind::Gtri } |
+        tag!("gtrr") => { |_| InstructionKind::Gtrr } |
+        tag!("eqir") => { |_| InstructionKind::Eqir } |
+        tag!("eqri") => { |_| InstructionKind::Eqri } |
+        tag!("eqrr") => { |_| InstructionKind::Eqrr }
+    )
+);
+
+#[rustfmt::skip]
+named!(
+    integer(CompleteStr<'_>) -> usize,
+    map_res!(take_while!(|c| char::is_digit(c, 10)), |x: CompleteStr<'_>| x.parse())
+);
+
+#[cfg(test)]
+mod test {
+    use super::{get_cpu, load_numeric, Arithmetic, Backend, InstructionKind, Overflow};
+    use crate::cpu::threaded::Threaded;
+    use crate::lines;
+    use std::collections::HashMap;
+
+    #[test]
+    fn applies_arithmetic_modes() {
+        let program = lines!(
+            "#ip 5"
+            "seti 16777215 0 0"
+            "addi 0 2 0"
+            "seti 18446744073709551615 0 1"
+            "muli 1 2 1"
+        );
+        let run = |arithmetic| {
+            let cpu = get_cpu(program, [0; 6])
+                .unwrap()
+                .with_arithmetic(arithmetic);
+            let mut threaded = Threaded::new(&cpu).unwrap();
+            let mut cpu = cpu;
+            let result = cpu.run().map(|_| cpu.registers);
+            assert_eq!(threaded.run().map(|_| *threaded.registers()), result);
+            result
+        };
+        let error = run(Arithmetic::Checked).unwrap_err();
+        assert_eq!(
+            error,
+            Overflow {
+                address: 3,
+                instruction: get_cpu(program, [0; 6]).unwrap().instructions()[3],
+            }
+        );
+        assert_eq!(error.to_string(), "Overflow at ip 3 running muli 1 2 1");
+        assert_eq!(
+            run(Arithmetic::Wrapping),
+            Ok([16_777_217, usize::MAX - 1, 0, 0, 0, 4])
+        );
+        assert_eq!(run(Arithmetic::Bits24), Ok([1, 16_777_214, 0, 0, 0, 4]));
+        assert_eq!(
+            run(Arithmetic::Bits32),
+            Ok([16_777_217, 4_294_967_294, 0, 0, 0, 4])
+        );
+        assert_eq!(run(Arithmetic::Bits64), run(Arithmetic::Wrapping));
+    }
+
+    #[test]
+    fn loads_numeric_opcodes() {
+        let opcodes: HashMap<_, _> = InstructionKind::ALL
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(opcode, &kind)| (opcode, kind))
+            .collect();
+        // seti 3 0 1, addi 1 2 2, mulr 2 1 0
+        let program = [[6, 3, 0, 1], [14, 1, 2, 2], [13, 2, 1, 0]];
+        let mut cpu = load_numeric(&program, &opcodes, [0; 4]).unwrap();
+        assert_eq!(cpu.ip_binding(), None);
+        while cpu.step() {}
+        assert_eq!(cpu.registers, [15, 3, 5, 0]);
+        assert_eq!(cpu.address(), 3);
+        let error = |program: &[[usize; 4]]| {
+            load_numeric(program, &opcodes, [0; 4])
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(error(&[[16, 0, 0, 0]]), "Unknown opcode 16");
+        assert_eq!(
+            error(&[[6, 3, 0, 4]]),
+            "Register 4 doesn't exist with 4 registers"
+        );
+    }
+}
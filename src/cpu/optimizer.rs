@@ -0,0 +1,449 @@
+use super::{Backend, InstructionKind, Overflow, CPU};
+use std::ops::Range;
+
+/// Loop with a known closed form. Fields are the registers the loop uses.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Idiom {
+    /// Two nested loops adding every `outer` with `outer * inner == target` to `sum`, which is
+    /// the sum of the divisors of `target`.
+    DivisorSum {
+        sum: usize,
+        outer: usize,
+        inner: usize,
+        temporary: usize,
+        target: usize,
+    },
+    /// Counts `quotient` up from 0 until the next multiple of `divisor` exceeds `dividend`.
+    Division {
+        quotient: usize,
+        temporary: usize,
+        dividend: usize,
+        divisor: usize,
+    },
+}
+
+/// Idiom found in a program. It runs from the first address to the end of the range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Found {
+    pub addresses: Range<usize>,
+    pub idiom: Idiom,
+}
+
+/// Parameter of an instruction in a pattern.
+#[derive(Copy, Clone)]
+enum P {
+    /// Register bound on first use, distinct from other registers and the instruction pointer.
+    Register(usize),
+    Ip,
+    /// Immediate value bound on first use.
+    Constant(usize),
+    Literal(usize),
+    /// Immediate jump target relative to the start of the pattern.
+    Address(usize),
+    Any,
+}
+
+type Pattern = &'static [(InstructionKind, [P; 3])];
+
+const DIVISOR_SUM: Pattern = {
+    use self::InstructionKind::*;
+    use self::P::*;
+    let (sum, outer, inner, temporary, target) = (0, 1, 2, 3, 4);
+    &[
+        (Seti, [Literal(1), Any, Register(outer)]),
+        (Seti, [Literal(1), Any, Register(inner)]),
+        (
+            Mulr,
+            [Register(outer), Register(inner), Register(temporary)],
+        ),
+        (
+            Eqrr,
+            [Register(temporary), Register(target), Register(temporary)],
+        ),
+        (Addr, [Register(temporary), Ip, Ip]),
+        (Addi, [Ip, Literal(1), Ip]),
+        (Addr, [Register(outer), Register(sum), Register(sum)]),
+        (Addi, [Register(inner), Literal(1), Register(inner)]),
+        (
+            Gtrr,
+            [Register(inner), Register(target), Register(temporary)],
+        ),
+        (Addr, [Ip, Register(temporary), Ip]),
+        (Seti, [Address(1), Any, Ip]),
+        (Addi, [Register(outer), Literal(1), Register(outer)]),
+        (
+            Gtrr,
+            [Register(outer), Register(target), Register(temporary)],
+        ),
+        (Addr, [Register(temporary), Ip, Ip]),
+        (Seti, [Address(0), Any, Ip]),
+    ]
+};
+
+const DIVISION: Pattern = {
+    use self::InstructionKind::*;
+    use self::P::*;
+    let (quotient, temporary, dividend, divisor) = (0, 1, 2, 0);
+    &[
+        (Seti, [Literal(0), Any, Register(quotient)]),
+        (Addi, [Register(quotient), Literal(1), Register(temporary)]),
+        (
+            Muli,
+            [Register(temporary), Constant(divisor), Register(temporary)],
+        ),
+        (
+            Gtrr,
+            [Register(temporary), Register(dividend), Register(temporary)],
+        ),
+        (Addr, [Register(temporary), Ip, Ip]),
+        (Addi, [Ip, Literal(1), Ip]),
+        (Seti, [Address(8), Any, Ip]),
+        (Addi, [Register(quotient), Literal(1), Register(quotient)]),
+        (Seti, [Address(0), Any, Ip]),
+    ]
+};
+
+#[derive(Clone, Default)]
+struct Bindings {
+    registers: [Option<usize>; 5],
+    constants: [Option<usize>; 1],
+}
+
+impl Bindings {
+    fn bind(&mut self, p: P, value: usize, ip: usize, start: usize) -> bool {
+        match p {
+            P::Register(var) => match self.registers[var] {
+                Some(register) => register == value,
+                None => {
+                    if value == ip || value >= 6 || self.registers.contains(&Some(value)) {
+                        return false;
+                    }
+                    self.registers[var] = Some(value);
+                    true
+                }
+            },
+            P::Ip => value == ip,
+            P::Constant(var) => *self.constants[var].get_or_insert(value) == value,
+            P::Literal(literal) => value == literal,
+            P::Address(offset) => value == start + offset,
+            P::Any => true,
+        }
+    }
+
+    fn register(&self, var: usize) -> usize {
+        self.registers[var].unwrap()
+    }
+}
+
+fn is_commutative(kind: InstructionKind) -> bool {
+    use self::InstructionKind::*;
+    matches!(kind, Addr | Mulr | Banr | Borr | Eqrr)
+}
+
+/// Matches a pattern at an address, operands of commutative instructions may be swapped.
+/// Patterns loop through the instruction pointer, so nothing matches when it isn't bound.
+fn match_pattern(cpu: &CPU, start: usize, pattern: Pattern) -> Option<Bindings> {
+    let ip = cpu.ip_binding()?;
+    let instructions = cpu.instructions().get(start..start + pattern.len())?;
+    let mut bindings = Bindings::default();
+    for (instruction, &(kind, parameters)) in instructions.iter().zip(pattern) {
+        if instruction.kind != kind {
+            return None;
+        }
+        let [a, b, c] = instruction.parameters;
+        let mut orders = vec![[a, b, c]];
+        if is_commutative(kind) {
+            orders.push([b, a, c]);
+        }
+        bindings = orders.into_iter().find_map(|values| {
+            let mut candidate = bindings.clone();
+            parameters
+                .iter()
+                .zip(&values)
+                .all(|(&p, &value)| candidate.bind(p, value, ip, start))
+                .then_some(candidate)
+        })?;
+    }
+    Some(bindings)
+}
+
+/// Finds the idioms of a program, which don't overlap.
+pub fn find_idioms(cpu: &CPU) -> Vec<Found> {
+    let mut found = Vec::new();
+    let mut address = 0;
+    while address < cpu.instructions().len() {
+        let divisor_sum = || {
+            let b = match_pattern(cpu, address, DIVISOR_SUM)?;
+            let idiom = Idiom::DivisorSum {
+                sum: b.register(0),
+                outer: b.register(1),
+                inner: b.register(2),
+                temporary: b.register(3),
+                target: b.register(4),
+            };
+            Some((DIVISOR_SUM.len(), idiom))
+        };
+        let division = || {
+            let b = match_pattern(cpu, address, DIVISION)?;
+            let idiom = Idiom::Division {
+                quotient: b.register(0),
+                temporary: b.register(1),
+                dividend: b.register(2),
+                divisor: b.constants[0].unwrap(),
+            };
+            Some((DIVISION.len(), idiom))
+        };
+        let idiom = divisor_sum().or_else(division);
+        match idiom {
+            Some((length, idiom)) => {
+                found.push(Found {
+                    addresses: address..address + length,
+                    idiom,
+                });
+                address += length;
+            }
+            None => address += 1,
+        }
+    }
+    found
+}
+
+fn sum_of_divisors(n: usize) -> Option<usize> {
+    let mut sum = 0usize;
+    let mut factor = 1;
+    while factor * factor <= n {
+        if n.is_multiple_of(factor) {
+            let other = n / factor;
+            sum = sum.checked_add(factor)?;
+            if other != factor {
+                sum = sum.checked_add(other)?;
+            }
+        }
+        factor += 1;
+    }
+    Some(sum)
+}
+
+impl Idiom {
+    /// Runs the loop in closed form, `None` when the result could differ from stepping, such as
+    /// when it wouldn't terminate or would go over the largest value of a register.
+    pub fn apply(&self, registers: &mut [usize; 6], limit: usize) -> Option<()> {
+        let fits = |value: Option<usize>| value.filter(|&v| v <= limit);
+        match *self {
+            Idiom::DivisorSum {
+                sum,
+                outer,
+                inner,
+                temporary,
+                target,
+            } => {
+                // Both loops run at least once, up to the target.
+                let last = registers[target].max(1);
+                fits(last.checked_mul(last))?;
+                let next = fits(last.checked_add(1))?;
+                let added = if registers[target] == 0 {
+                    0
+                } else {
+                    sum_of_divisors(registers[target])?
+                };
+                registers[sum] = fits(registers[sum].checked_add(added))?;
+                registers[outer] = next;
+                registers[inner] = next;
+                registers[temporary] = 1;
+            }
+            Idiom::Division {
+                quotient,
+                temporary,
+                dividend,
+                divisor,
+            } => {
+                let value = fits(Some(registers[dividend]))?.checked_div(divisor)?;
+                fits(value.checked_add(1)?.checked_mul(divisor))?;
+                registers[quotient] = value;
+                registers[temporary] = 1;
+            }
+        }
+        Some(())
+    }
+}
+
+/// Runs a program, replacing idioms entered at their first instruction with their closed form
+/// and stepping through everything else with another backend.
+#[derive(Clone, Debug)]
+pub struct Optimizer<B = CPU> {
+    backend: B,
+    found: Vec<Found>,
+    /// Index in `found` of the idiom starting at each address.
+    starts: Vec<Option<usize>>,
+    /// Largest value of a register with the arithmetic of the program.
+    limit: usize,
+}
+
+impl Optimizer {
+    pub fn new(cpu: CPU) -> Self {
+        let found = find_idioms(&cpu);
+        let limit = cpu.arithmetic().max();
+        Self::with_found(cpu.instructions().len(), limit, found, cpu)
+    }
+}
+
+impl<B: Backend> Optimizer<B> {
+    /// Optimizes a program, running it with a backend compiled from it.
+    pub fn with_backend(cpu: &CPU, backend: B) -> Self {
+        let limit = cpu.arithmetic().max();
+        Self::with_found(cpu.instructions().len(), limit, find_idioms(cpu), backend)
+    }
+
+    fn with_found(length: usize, limit: usize, found: Vec<Found>, backend: B) -> Self {
+        let mut starts = vec![None; length];
+        for (i, f) in found.iter().enumerate() {
+            starts[f.addresses.start] = Some(i);
+        }
+        Optimizer {
+            backend,
+            found,
+            starts,
+            limit,
+        }
+    }
+
+    pub fn found(&self) -> &[Found] {
+        &self.found
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn into_backend(self) -> B {
+        self.backend
+    }
+}
+
+impl<B: Backend> Backend for Optimizer<B> {
+    /// Runs one instruction or one whole idiom.
+    fn step(&mut self) -> bool {
+        let address = self.backend.address();
+        // Idioms are only found in programs which bind the instruction pointer.
+        if let (Some(&Some(i)), Some(ip)) = (self.starts.get(address), self.backend.ip_binding()) {
+            let found = &self.found[i];
+            let mut registers = *self.backend.registers();
+            if found.idiom.apply(&mut registers, self.limit).is_some() {
+                registers[ip] = found.addresses.end;
+                *self.backend.registers_mut() = registers;
+                return true;
+            }
+        }
+        self.backend.step()
+    }
+
+    fn registers(&self) -> &[usize; 6] {
+        self.backend.registers()
+    }
+
+    fn registers_mut(&mut self) -> &mut [usize; 6] {
+        self.backend.registers_mut()
+    }
+
+    fn address(&self) -> usize {
+        self.backend.address()
+    }
+
+    fn ip_binding(&self) -> Option<usize> {
+        self.backend.ip_binding()
+    }
+
+    fn overflow(&self) -> Option<Overflow> {
+        self.backend.overflow()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_idioms, Idiom, Optimizer};
+    use crate::cpu::threaded::Threaded;
+    use crate::cpu::{get_cpu, Backend};
+
+    #[test]
+    fn finds_idioms_in_bundled_programs() {
+        let day19 = get_cpu(include_str!("../day19/input"), [0; 6]).unwrap();
+        let found = find_idioms(&day19);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].addresses, 1..16);
+        assert_eq!(
+            found[0].idiom,
+            Idiom::DivisorSum {
+                sum: 0,
+                outer: 1,
+                inner: 5,
+                temporary: 3,
+                target: 2
+            }
+        );
+        let day21 = get_cpu(include_str!("../day21/input"), [0; 6]).unwrap();
+        let found = find_idioms(&day21);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].addresses, 17..26);
+        assert_eq!(
+            found[0].idiom,
+            Idiom::Division {
+                quotient: 3,
+                temporary: 1,
+                dividend: 5,
+                divisor: 256
+            }
+        );
+    }
+
+    #[test]
+    fn matches_stepping() {
+        let program = include_str!("../day19/input");
+        for target in [0, 1, 2, 12, 36, 97] {
+            // Starts right at the idiom.
+            let registers = [3, 0, target, 0, 1, 0];
+            let mut plain = get_cpu(program, registers).unwrap();
+            while plain.step() {}
+            let cpu = get_cpu(program, registers).unwrap();
+            let mut threaded = Optimizer::with_backend(&cpu, Threaded::new(&cpu).unwrap());
+            let mut optimizer = Optimizer::new(cpu);
+            optimizer.run().unwrap();
+            threaded.run().unwrap();
+            assert_eq!(optimizer.registers(), &plain.registers, "{}", target);
+            assert_eq!(threaded.registers(), &plain.registers, "{}", target);
+        }
+        let program = include_str!("../day21/input");
+        for dividend in [0, 1, 255, 256, 257, 70_000] {
+            let registers = [0, 0, 0, 0, 17, dividend];
+            let mut plain = get_cpu(program, registers).unwrap();
+            let mut optimizer = Optimizer::new(get_cpu(program, registers).unwrap());
+            while plain.registers[4] != 26 {
+                plain.step();
+            }
+            optimizer.step();
+            assert_eq!(optimizer.registers(), &plain.registers, "{}", dividend);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_stepping() {
+        // Dividing by 0 never terminates, the loop is stepped through instead.
+        let program = include_str!("../day21/input").replace("muli 1 256 1", "muli 1 0 1");
+        let mut optimizer = Optimizer::new(get_cpu(&program, [0, 0, 0, 0, 17, 10]).unwrap());
+        assert_eq!(optimizer.found().len(), 1);
+        for _ in 0..100 {
+            optimizer.step();
+        }
+        assert!((17..26).contains(&optimizer.registers()[4]));
+    }
+
+    #[test]
+    fn stays_within_register_width() {
+        let idiom = find_idioms(&get_cpu(include_str!("../day19/input"), [0; 6]).unwrap())[0].idiom;
+        let limit = (1 << 24) - 1;
+        // The loops multiply every pair of numbers up to the target.
+        let mut registers = [0, 0, 4095, 0, 1, 0];
+        assert_eq!(idiom.apply(&mut registers, limit), Some(()));
+        let mut registers = [0, 0, 4096, 0, 1, 0];
+        assert_eq!(idiom.apply(&mut registers, limit), None);
+    }
+}
@@ -0,0 +1,590 @@
+use super::disassembler::Operand;
+use super::{Arithmetic, Instruction, InstructionKind, CPU};
+use std::collections::BTreeMap;
+use std::error::Error;
+use z3::{Ast, Config, Context, Solver};
+
+/// Place a path has to get to for its starting registers to be a solution.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Goal {
+    /// The instruction pointer leaves the program.
+    Halt,
+    /// The instruction at this address is about to run.
+    Address(usize),
+}
+
+/// Starting registers which reach a goal, and the number of instructions run to get there.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub registers: [usize; 6],
+    pub steps: u64,
+}
+
+/// Runs a program with some registers standing for any value, forking whenever a comparison
+/// depends on them. Paths are explored in order of instructions run, so the solution found
+/// reaches the goal as soon as possible.
+pub struct Symbolic<'a> {
+    cpu: &'a CPU,
+    symbolic: Vec<usize>,
+    max_steps: u64,
+}
+
+impl<'a> Symbolic<'a> {
+    pub fn new(cpu: &'a CPU) -> Self {
+        Symbolic {
+            cpu,
+            symbolic: Vec::new(),
+            max_steps: 1_000_000,
+        }
+    }
+
+    /// Treats the starting value of a register as unknown.
+    pub fn symbolic(mut self, register: usize) -> Self {
+        self.symbolic.push(register);
+        self
+    }
+
+    /// Gives up on paths running more instructions than this.
+    pub fn up_to(mut self, max_steps: u64) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Finds starting values of the symbolic registers reaching a goal, `None` when no path
+    /// does within the bound.
+    pub fn find(&self, goal: Goal) -> Result<Option<Solution>, Box<dyn Error>> {
+        let ctx = Context::new(&Config::new());
+        let machine = Machine {
+            ctx: &ctx,
+            solver: Solver::new(&ctx),
+            arithmetic: self.cpu.arithmetic(),
+            instructions: self.cpu.instructions(),
+            ip: self.cpu.ip_register()?,
+        };
+        let mut registers = self.cpu.registers.map(Value::Concrete);
+        let mut constraints = Vec::new();
+        let mut variables = Vec::new();
+        for &register in &self.symbolic {
+            let variable = ctx.named_int_const(&format!("r{}", register));
+            constraints.push(variable.ge(&machine.int(0)));
+            constraints.push(variable.le(&machine.max()));
+            registers[register] = Value::Symbolic(variable.clone());
+            variables.push((register, variable));
+        }
+        // Paths waiting to run, by instructions run and then order of creation.
+        let mut queue = BTreeMap::new();
+        let mut created = 0;
+        queue.insert(
+            (0, created),
+            Pending::Running(Path {
+                registers,
+                constraints,
+                steps: 0,
+            }),
+        );
+        while let Some((_, pending)) = queue.pop_first() {
+            let path = match pending {
+                Pending::Reached(path) => {
+                    match machine.solution(&path, self.cpu.registers, &variables)? {
+                        Some(solution) => return Ok(Some(solution)),
+                        None => continue,
+                    }
+                }
+                Pending::Running(path) => path,
+            };
+            for path in machine.concretize_ip(path) {
+                let address = machine.address(&path);
+                let halted = address >= machine.instructions.len();
+                let reached = match goal {
+                    Goal::Halt => halted,
+                    Goal::Address(target) => address == target,
+                };
+                created += 1;
+                if reached {
+                    queue.insert((path.steps, created), Pending::Reached(path));
+                } else if !halted && path.steps < self.max_steps {
+                    for next in machine.step(path, machine.instructions[address]) {
+                        created += 1;
+                        queue.insert((next.steps, created), Pending::Running(next));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+enum Pending<'ctx> {
+    Running(Path<'ctx>),
+    Reached(Path<'ctx>),
+}
+
+#[derive(Clone)]
+enum Value<'ctx> {
+    Concrete(usize),
+    Symbolic(Ast<'ctx>),
+}
+
+#[derive(Clone)]
+struct Path<'ctx> {
+    registers: [Value<'ctx>; 6],
+    /// Conditions the symbolic registers met to take this path.
+    constraints: Vec<Ast<'ctx>>,
+    steps: u64,
+}
+
+struct Machine<'ctx> {
+    ctx: &'ctx Context,
+    solver: Solver<'ctx>,
+    arithmetic: Arithmetic,
+    instructions: &'ctx [Instruction],
+    ip: usize,
+}
+
+impl<'ctx> Machine<'ctx> {
+    fn int(&self, value: usize) -> Ast<'ctx> {
+        Ast::from_u64(self.ctx, value as u64)
+    }
+
+    fn power_of_two(&self, exponent: u32) -> Ast<'ctx> {
+        // Numerals are limited to 64 bits.
+        if exponent < 64 {
+            self.int(1 << exponent)
+        } else {
+            self.power_of_two(32)
+                .mul(&[&self.power_of_two(exponent - 32)])
+        }
+    }
+
+    fn width(&self) -> u32 {
+        self.arithmetic.bits().unwrap_or(usize::BITS)
+    }
+
+    /// Largest value a register holds.
+    fn max(&self) -> Ast<'ctx> {
+        self.int(self.arithmetic.max())
+    }
+
+    fn ast(&self, value: &Value<'ctx>) -> Ast<'ctx> {
+        match value {
+            Value::Concrete(value) => self.int(*value),
+            Value::Symbolic(ast) => ast.clone(),
+        }
+    }
+
+    fn feasible(&self, constraints: &[Ast<'ctx>]) -> bool {
+        self.solver.push();
+        for constraint in constraints {
+            self.solver.assert(constraint);
+        }
+        let feasible = self.solver.check();
+        self.solver.pop(1);
+        feasible
+    }
+
+    /// Address of the next instruction of a path whose instruction pointer is concrete. Any
+    /// address past the program halts it the same way.
+    fn address(&self, path: &Path<'ctx>) -> usize {
+        match path.registers[self.ip] {
+            Value::Concrete(address) => address,
+            Value::Symbolic(_) => unreachable!("instruction pointer is symbolic"),
+        }
+    }
+
+    /// Splits a path on every address its instruction pointer can be at.
+    fn concretize_ip(&self, path: Path<'ctx>) -> Vec<Path<'ctx>> {
+        let ip = match &path.registers[self.ip] {
+            Value::Concrete(_) => return vec![path],
+            Value::Symbolic(ip) => ip.clone(),
+        };
+        let length = self.instructions.len();
+        let mut cases: Vec<_> = (0..length)
+            .map(|address| (ip._eq(&self.int(address)), address))
+            .collect();
+        cases.push((ip.ge(&self.int(length)), length));
+        cases
+            .into_iter()
+            .filter_map(|(condition, address)| {
+                let mut path = path.clone();
+                path.constraints.push(condition);
+                path.registers[self.ip] = Value::Concrete(address);
+                Some(path).filter(|path| self.feasible(&path.constraints))
+            })
+            .collect()
+    }
+
+    /// Runs an instruction, returning the paths which follow it. Paths overflowing with checked
+    /// arithmetic stop without halting.
+    fn step(&self, mut path: Path<'ctx>, instruction: Instruction) -> Vec<Path<'ctx>> {
+        let operand = |operand| match operand {
+            Operand::Register(register) => path.registers[register].clone(),
+            Operand::Immediate(value) => Value::Concrete(value),
+        };
+        let (a, b) = instruction.operands();
+        let x = operand(a);
+        let y = b.map(operand).unwrap_or(Value::Concrete(0));
+        let mut constraints = Vec::new();
+        use self::InstructionKind::*;
+        let value = match instruction.kind {
+            Addr | Addi => self.add(&x, &y, &mut constraints),
+            Mulr | Muli => self.multiply(&x, &y, &mut constraints),
+            Banr | Bani => Some(self.bitwise(&x, &y, Bitwise::And)),
+            Borr | Bori => Some(self.bitwise(&x, &y, Bitwise::Or)),
+            Setr | Seti => Some(match x {
+                Value::Concrete(value) => Value::Concrete(self.arithmetic.truncate(value)),
+                symbolic => symbolic,
+            }),
+            Gtir | Gtri | Gtrr => {
+                return self.compare(path, instruction, &x, &y, Comparison::Greater)
+            }
+            Eqir | Eqri | Eqrr => {
+                return self.compare(path, instruction, &x, &y, Comparison::Equal)
+            }
+        };
+        // Requiring a result to fit in a register can rule the path out.
+        if !constraints.is_empty() {
+            path.constraints.extend(constraints);
+            if !self.feasible(&path.constraints) {
+                return Vec::new();
+            }
+        }
+        value
+            .and_then(|value| self.write(path, instruction, value))
+            .into_iter()
+            .collect()
+    }
+
+    /// Sets a register to whether a comparison holds, forking the path when it depends on
+    /// symbolic registers.
+    fn compare(
+        &self,
+        path: Path<'ctx>,
+        instruction: Instruction,
+        x: &Value<'ctx>,
+        y: &Value<'ctx>,
+        comparison: Comparison,
+    ) -> Vec<Path<'ctx>> {
+        if let (Value::Concrete(x), Value::Concrete(y)) = (x, y) {
+            let holds = match comparison {
+                Comparison::Greater => x > y,
+                Comparison::Equal => x == y,
+            };
+            let value = Value::Concrete(holds.into());
+            return self.write(path, instruction, value).into_iter().collect();
+        }
+        let (x, y) = (self.ast(x), self.ast(y));
+        let condition = match comparison {
+            Comparison::Greater => x.gt(&y),
+            Comparison::Equal => x._eq(&y),
+        };
+        vec![(condition.not(), 0), (condition, 1)]
+            .into_iter()
+            .filter_map(|(condition, value)| {
+                let mut path = path.clone();
+                path.constraints.push(condition);
+                if !self.feasible(&path.constraints) {
+                    return None;
+                }
+                self.write(path, instruction, Value::Concrete(value))
+            })
+            .collect()
+    }
+
+    /// Stores the result of an instruction and moves to the next one.
+    fn write(
+        &self,
+        mut path: Path<'ctx>,
+        instruction: Instruction,
+        value: Value<'ctx>,
+    ) -> Option<Path<'ctx>> {
+        path.registers[instruction.parameters[2]] = value;
+        let mut constraints = Vec::new();
+        let ip = self.add(
+            &path.registers[self.ip],
+            &Value::Concrete(1),
+            &mut constraints,
+        )?;
+        path.registers[self.ip] = ip;
+        path.steps += 1;
+        if constraints.is_empty() {
+            return Some(path);
+        }
+        path.constraints.extend(constraints);
+        Some(path).filter(|path| self.feasible(&path.constraints))
+    }
+
+    /// Makes a result fit in a register, requiring it to with checked arithmetic.
+    fn fit(&self, value: Ast<'ctx>, constraints: &mut Vec<Ast<'ctx>>) -> Ast<'ctx> {
+        match self.arithmetic {
+            Arithmetic::Checked => {
+                constraints.push(value.le(&self.max()));
+                value
+            }
+            _ => value.modulo(&self.power_of_two(self.width())),
+        }
+    }
+
+    fn add(
+        &self,
+        x: &Value<'ctx>,
+        y: &Value<'ctx>,
+        constraints: &mut Vec<Ast<'ctx>>,
+    ) -> Option<Value<'ctx>> {
+        match (x, y) {
+            (Value::Concrete(x), Value::Concrete(y)) => {
+                self.arithmetic.add(*x, *y).map(Value::Concrete)
+            }
+            _ => {
+                let sum = self.ast(x).add(&[&self.ast(y)]);
+                Some(Value::Symbolic(self.fit(sum, constraints)))
+            }
+        }
+    }
+
+    fn multiply(
+        &self,
+        x: &Value<'ctx>,
+        y: &Value<'ctx>,
+        constraints: &mut Vec<Ast<'ctx>>,
+    ) -> Option<Value<'ctx>> {
+        match (x, y) {
+            (Value::Concrete(x), Value::Concrete(y)) => {
+                self.arithmetic.multiply(*x, *y).map(Value::Concrete)
+            }
+            _ => {
+                let product = self.ast(x).mul(&[&self.ast(y)]);
+                Some(Value::Symbolic(self.fit(product, constraints)))
+            }
+        }
+    }
+
+    /// Bits of a value at the positions set in a mask, as a sum of the runs of set bits.
+    fn mask(&self, value: &Ast<'ctx>, mask: usize) -> Ast<'ctx> {
+        let mask = self.arithmetic.truncate(mask);
+        let mut sum = self.int(0);
+        let mut low = 0;
+        while low < usize::BITS {
+            if mask >> low & 1 == 0 {
+                low += 1;
+                continue;
+            }
+            let high = (low..usize::BITS)
+                .find(|&i| mask >> i & 1 == 0)
+                .unwrap_or(usize::BITS);
+            let run = value
+                .div(&self.power_of_two(low))
+                .modulo(&self.power_of_two(high - low))
+                .mul(&[&self.power_of_two(low)]);
+            sum = sum.add(&[&run]);
+            low = high;
+        }
+        sum
+    }
+
+    /// Bitwise operation, using integer arithmetic as the solver's integers have no bits.
+    fn bitwise(&self, x: &Value<'ctx>, y: &Value<'ctx>, operation: Bitwise) -> Value<'ctx> {
+        let (x, y) = match (x, y) {
+            (Value::Concrete(x), Value::Concrete(y)) => {
+                let value = match operation {
+                    Bitwise::And => x & y,
+                    Bitwise::Or => x | y,
+                };
+                return Value::Concrete(self.arithmetic.truncate(value));
+            }
+            (Value::Symbolic(x), Value::Concrete(y)) | (Value::Concrete(y), Value::Symbolic(x)) => {
+                let and = self.mask(x, *y);
+                return Value::Symbolic(match operation {
+                    Bitwise::And => and,
+                    Bitwise::Or => x
+                        .add(&[&self.int(self.arithmetic.truncate(*y))])
+                        .sub(&[&and]),
+                });
+            }
+            (Value::Symbolic(x), Value::Symbolic(y)) => (x, y),
+        };
+        // Both sides are unknown, so go one bit at a time.
+        let mut sum = self.int(0);
+        for index in 0..self.width() {
+            let bit = |value: &Ast<'ctx>| value.div(&self.power_of_two(index)).modulo(&self.int(2));
+            let (x, y) = (bit(x), bit(y));
+            let and = x.mul(&[&y]);
+            let bit = match operation {
+                Bitwise::And => and,
+                Bitwise::Or => x.add(&[&y]).sub(&[&and]),
+            };
+            sum = sum.add(&[&bit.mul(&[&self.power_of_two(index)])]);
+        }
+        Value::Symbolic(sum)
+    }
+
+    /// Starting values of the symbolic registers taking a path, `None` if none do.
+    fn solution(
+        &self,
+        path: &Path<'ctx>,
+        mut registers: [usize; 6],
+        variables: &[(usize, Ast<'ctx>)],
+    ) -> Result<Option<Solution>, Box<dyn Error>> {
+        self.solver.push();
+        for constraint in &path.constraints {
+            self.solver.assert(constraint);
+        }
+        let values: Result<Option<Vec<_>>, Box<dyn Error>> = if self.solver.check() {
+            let model = self.solver.get_model();
+            variables
+                .iter()
+                .map(|(register, variable)| {
+                    let value = model
+                        .eval(variable)
+                        .ok_or("Variable not available")?
+                        .as_u64()
+                        .ok_or("Variable not obtainable as u64")?;
+                    Ok((*register, value as usize))
+                })
+                .collect::<Result<_, _>>()
+                .map(Some)
+        } else {
+            Ok(None)
+        };
+        self.solver.pop(1);
+        Ok(values?.map(|values| {
+            for (register, value) in values {
+                registers[register] = value;
+            }
+            Solution {
+                registers,
+                steps: path.steps,
+            }
+        }))
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Bitwise {
+    And,
+    Or,
+}
+
+#[derive(Copy, Clone)]
+enum Comparison {
+    Greater,
+    Equal,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Goal, Solution, Symbolic};
+    use crate::cpu::{get_cpu, load_numeric, Arithmetic, InstructionKind};
+    use crate::lines;
+
+    #[test]
+    fn solves_arithmetic() {
+        // Halts when 3 * r0 + 1 is 22, spinning at address 4 otherwise.
+        let program = lines!(
+            "#ip 4"
+            "muli 0 3 1"
+            "addi 1 1 1"
+            "eqri 1 22 1"
+            "addr 1 4 4"
+            "seti 3 0 4"
+            "seti 99 0 4"
+        );
+        for arithmetic in [Arithmetic::Checked, Arithmetic::Bits24] {
+            let cpu = get_cpu(program, [0; 6])
+                .unwrap()
+                .with_arithmetic(arithmetic);
+            let found = Symbolic::new(&cpu).symbolic(0).up_to(100).find(Goal::Halt);
+            assert_eq!(
+                found.unwrap(),
+                Some(Solution {
+                    registers: [7, 0, 0, 0, 0, 0],
+                    steps: 5,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn skips_paths_which_overflow() {
+        // With r0 > 1 the multiplication overflows, so only 0 and 1 halt.
+        let program = lines!(
+            "#ip 3"
+            "gtri 0 1 1"
+            "addr 1 3 3"
+            "seti 4 0 3"
+            "muli 0 9223372036854775808 2"
+            "seti 99 0 3"
+            "addi 2 1 2"
+            "addi 2 1 2"
+            "seti 99 0 3"
+        );
+        let cpu = get_cpu(program, [0; 6]).unwrap();
+        let found = Symbolic::new(&cpu).symbolic(0).up_to(100).find(Goal::Halt);
+        let found = found.unwrap().unwrap();
+        assert!(found.registers[0] <= 1, "{:?}", found);
+        assert_eq!(found.steps, 6);
+    }
+
+    #[test]
+    fn follows_symbolic_jumps() {
+        let program = lines!(
+            "#ip 4"
+            "addr 0 4 4"
+            "seti 9 0 4"
+            "seti 9 0 4"
+            "seti 9 0 4"
+        );
+        let cpu = get_cpu(program, [0; 6]).unwrap();
+        let symbolic = Symbolic::new(&cpu).symbolic(0);
+        let found = symbolic.find(Goal::Address(3)).unwrap().unwrap();
+        assert_eq!(found.registers[0], 2);
+        assert_eq!(found.steps, 1);
+        // Jumping past the program is faster than going through the other instructions.
+        let found = symbolic.find(Goal::Halt).unwrap().unwrap();
+        assert!(found.registers[0] >= 3);
+        assert_eq!(found.steps, 1);
+    }
+
+    #[test]
+    fn solves_bitwise() {
+        // Halts when bit 3 of r0 is set.
+        let program = lines!(
+            "#ip 4"
+            "bori 0 5 1"
+            "bani 1 12 1"
+            "eqri 1 12 1"
+            "addr 1 4 4"
+            "seti 3 0 4"
+            "seti 99 0 4"
+        );
+        let cpu = get_cpu(program, [0; 6]).unwrap();
+        let found = Symbolic::new(&cpu).symbolic(0).up_to(100).find(Goal::Halt);
+        let found = found.unwrap().unwrap();
+        assert_eq!(found.registers[0] & 8, 8);
+        assert_eq!(found.steps, 5);
+    }
+
+    #[test]
+    fn gives_up_at_bound() {
+        let cpu = get_cpu(lines!("#ip 1" "eqri 0 1000 2" "seti 0 0 1"), [0; 6]).unwrap();
+        let symbolic = Symbolic::new(&cpu).symbolic(0).up_to(1000);
+        assert_eq!(symbolic.find(Goal::Halt).unwrap(), None);
+    }
+
+    #[test]
+    fn halts_day21_soonest() {
+        let cpu = get_cpu(include_str!("../day21/input"), [0; 6]).unwrap();
+        let found = Symbolic::new(&cpu).symbolic(0).find(Goal::Halt);
+        assert_eq!(found.unwrap().unwrap().registers[0], 12_980_435);
+    }
+
+    #[test]
+    fn needs_bound_ip() {
+        let opcodes = vec![(0, InstructionKind::Seti)].into_iter().collect();
+        let cpu = load_numeric(&[[0, 1, 0, 0]], &opcodes, [0; 6]).unwrap();
+        let error = Symbolic::new(&cpu).find(Goal::Halt).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The instruction pointer isn't bound to a register"
+        );
+    }
+}
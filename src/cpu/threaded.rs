@@ -0,0 +1,182 @@
+use super::disassembler::Operand;
+use super::{Backend, Instruction, InstructionKind, Overflow, UnboundIp, CPU};
+
+/// Runs an instruction, `None` if it overflows, leaving the registers untouched.
+type Operation = Box<dyn Fn(&mut [usize; 6]) -> Option<()>>;
+
+/// Program decoded ahead of time into one specialised closure per instruction. Jumps to a
+/// constant address are resolved while compiling.
+pub struct Threaded {
+    ip: usize,
+    registers: [usize; 6],
+    instructions: Vec<Instruction>,
+    operations: Vec<Operation>,
+    overflow: Option<Overflow>,
+}
+
+impl Threaded {
+    /// Compiles a program, jumps are resolved through the register bound to the instruction
+    /// pointer, so it must have one.
+    pub fn new(cpu: &CPU) -> Result<Self, UnboundIp> {
+        let ip = cpu.ip_register()?;
+        let arithmetic = cpu.arithmetic();
+        let operations = cpu
+            .instructions()
+            .iter()
+            .enumerate()
+            .map(|(address, instruction)| {
+                let [a, b, c] = instruction.parameters;
+                let constant = |operand| match operand {
+                    Operand::Register(register) if register == ip => Some(address),
+                    Operand::Register(_) => None,
+                    Operand::Immediate(value) => Some(value),
+                };
+                let (first, second) = instruction.operands();
+                let is_constant =
+                    constant(first).is_some() && second.is_none_or(|s| constant(s).is_some());
+                if c == ip && is_constant {
+                    let mut registers = [0; 6];
+                    registers[ip] = address;
+                    let target = instruction
+                        .run(registers, arithmetic)
+                        .and_then(|value| arithmetic.add(value, 1));
+                    return Box::new(move |r: &mut [usize; 6]| {
+                        r[ip] = target?;
+                        Some(())
+                    }) as Operation;
+                }
+                macro_rules! operation {
+                    (|$r:ident| $value:expr) => {
+                        Box::new(move |$r: &mut [usize; 6]| {
+                            let value = $value;
+                            let next = arithmetic.add(if c == ip { value } else { $r[ip] }, 1)?;
+                            $r[c] = value;
+                            $r[ip] = next;
+                            Some(())
+                        })
+                    };
+                }
+                let t = move |value| arithmetic.truncate(value);
+                use self::InstructionKind::*;
+                match instruction.kind {
+                    Addr => operation!(|r| arithmetic.add(r[a], r[b])?),
+                    Addi => operation!(|r| arithmetic.add(r[a], b)?),
+                    Mulr => operation!(|r| arithmetic.multiply(r[a], r[b])?),
+                    Muli => operation!(|r| arithmetic.multiply(r[a], b)?),
+                    Banr => operation!(|r| t(r[a] & r[b])),
+                    Bani => operation!(|r| t(r[a] & b)),
+                    Borr => operation!(|r| t(r[a] | r[b])),
+                    Bori => operation!(|r| t(r[a] | b)),
+                    Setr => operation!(|r| t(r[a])),
+                    Seti => operation!(|_r| t(a)),
+                    Gtir => operation!(|r| (a > r[b]).into()),
+                    Gtri => operation!(|r| (r[a] > b).into()),
+                    Gtrr => operation!(|r| (r[a] > r[b]).into()),
+                    Eqir => operation!(|r| (a == r[b]).into()),
+                    Eqri => operation!(|r| (r[a] == b).into()),
+                    Eqrr => operation!(|r| (r[a] == r[b]).into()),
+                }
+            })
+            .collect();
+        Ok(Threaded {
+            ip,
+            registers: cpu.registers,
+            instructions: cpu.instructions().to_vec(),
+            operations,
+            overflow: cpu.overflow(),
+        })
+    }
+}
+
+impl Backend for Threaded {
+    fn step(&mut self) -> bool {
+        if self.overflow.is_some() {
+            return false;
+        }
+        let address = self.registers[self.ip];
+        match self.operations.get(address) {
+            Some(operation) => {
+                if operation(&mut self.registers).is_none() {
+                    self.overflow = Some(Overflow {
+                        address,
+                        instruction: self.instructions[address],
+                    });
+                    return false;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn registers(&self) -> &[usize; 6] {
+        &self.registers
+    }
+
+    fn registers_mut(&mut self) -> &mut [usize; 6] {
+        &mut self.registers
+    }
+
+    fn address(&self) -> usize {
+        self.registers[self.ip]
+    }
+
+    fn ip_binding(&self) -> Option<usize> {
+        Some(self.ip)
+    }
+
+    fn overflow(&self) -> Option<Overflow> {
+        self.overflow
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Threaded;
+    use crate::cpu::{get_cpu, load_numeric, Backend, InstructionKind, UnboundIp};
+    use crate::lines;
+
+    fn trace(mut backend: impl Backend, steps: usize) -> Vec<[usize; 6]> {
+        let mut trace = vec![*backend.registers()];
+        for _ in 0..steps {
+            if !backend.step() {
+                break;
+            }
+            trace.push(*backend.registers());
+        }
+        trace
+    }
+
+    #[test]
+    fn matches_interpreter_trace() {
+        let programs = [
+            (include_str!("../day19/input"), [1, 0, 0, 0, 0, 0]),
+            (include_str!("../day21/input"), [0; 6]),
+            (
+                lines!(
+                    "#ip 0"
+                    "seti 5 0 1"
+                    "seti 6 0 2"
+                    "addi 0 1 0"
+                    "addr 1 2 3"
+                    "setr 1 0 0"
+                    "seti 8 0 4"
+                    "seti 9 0 5"
+                ),
+                [0; 6],
+            ),
+        ];
+        for (program, registers) in programs {
+            let cpu = get_cpu(program, registers).unwrap();
+            let threaded = Threaded::new(&cpu).unwrap();
+            assert_eq!(trace(threaded, 10_000), trace(cpu, 10_000));
+        }
+    }
+
+    #[test]
+    fn needs_bound_ip() {
+        let opcodes = vec![(0, InstructionKind::Seti)].into_iter().collect();
+        let cpu = load_numeric(&[[0, 1, 0, 0]], &opcodes, [0; 6]).unwrap();
+        assert_eq!(Threaded::new(&cpu).err(), Some(UnboundIp));
+    }
+}
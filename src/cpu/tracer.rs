@@ -0,0 +1,356 @@
+use super::{Backend, Instruction, Overflow, Snapshot, CPU};
+use crate::runner::Part;
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// One line per instruction with the registers before and after
+    Csv,
+    /// Fixed size little-endian records: the address as u32, the opcode as u8, the parameters
+    /// as 3 u32 and the registers before and after as 6 u64 each
+    Binary,
+}
+
+impl LogFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            LogFormat::Csv => "csv",
+            LogFormat::Binary => "bin",
+        }
+    }
+}
+
+/// Where traces of the programs of days 19 and 21 are written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    pub directory: PathBuf,
+    pub format: LogFormat,
+    /// Name of the traced input, if it has one, so that traces of inputs don't overwrite each
+    /// other.
+    pub input: Option<String>,
+}
+
+impl Trace {
+    /// Creates a tracer logging to `dayDD.partP.csv` or `.bin` in the directory, or to
+    /// `dayDD.NAME.partP.csv` for a named input. The summary is written next to it by
+    /// `Tracer::finish`.
+    pub fn create(&self, cpu: &CPU, day: u8, part: Part) -> io::Result<Tracer> {
+        fs::create_dir_all(&self.directory)?;
+        let name = match &self.input {
+            Some(input) => format!("day{:02}.{}.part{}", day, input, part),
+            None => format!("day{:02}.part{}", day, part),
+        };
+        let log = self
+            .directory
+            .join(format!("{}.{}", name, self.format.extension()));
+        let mut tracer = Tracer::new(cpu).with_log(Box::new(File::create(log)?), self.format)?;
+        tracer.summary = Some(self.directory.join(format!("{}.summary.txt", name)));
+        Ok(tracer)
+    }
+}
+
+/// Records executed instructions and counts how often each address runs.
+pub struct Tracer {
+    instructions: Vec<Instruction>,
+    log: Option<(BufWriter<Box<dyn Write>>, LogFormat)>,
+    /// First error writing the log, after which nothing more is written.
+    error: Option<io::Error>,
+    counts: Vec<u64>,
+    back_edges: BTreeMap<(usize, usize), u64>,
+    summary: Option<PathBuf>,
+}
+
+impl Tracer {
+    /// Tracer of a program which only counts instructions.
+    pub fn new(cpu: &CPU) -> Self {
+        Tracer {
+            instructions: cpu.instructions().to_vec(),
+            log: None,
+            error: None,
+            counts: vec![0; cpu.instructions().len()],
+            back_edges: BTreeMap::new(),
+            summary: None,
+        }
+    }
+
+    /// Also logs every instruction to a writer.
+    pub fn with_log(mut self, out: Box<dyn Write>, format: LogFormat) -> io::Result<Self> {
+        let mut out = BufWriter::new(out);
+        if format == LogFormat::Csv {
+            let registers = |prefix| (0..6).map(move |r| format!("{}_r{}", prefix, r));
+            let header: Vec<_> = registers("before").chain(registers("after")).collect();
+            writeln!(out, "ip,instruction,{}", header.join(","))?;
+        }
+        self.log = Some((out, format));
+        Ok(self)
+    }
+
+    /// Number of times the instruction at each address ran.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Jumps to the same or an earlier address by source and next address, with their counts.
+    pub fn back_edges(&self) -> &BTreeMap<(usize, usize), u64> {
+        &self.back_edges
+    }
+
+    pub fn record(&mut self, before: &Snapshot, after: &Snapshot) {
+        let address = before.address;
+        let instruction = match self.instructions.get(address) {
+            Some(&instruction) => instruction,
+            None => return,
+        };
+        self.counts[address] += 1;
+        if after.address <= address {
+            *self.back_edges.entry((address, after.address)).or_default() += 1;
+        }
+        if let (Some((out, format)), None) = (&mut self.log, &self.error) {
+            let (before, after) = (&before.registers, &after.registers);
+            if let Err(e) = write_record(out, *format, address, instruction, before, after) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Writes the hottest instructions and back edges.
+    pub fn write_summary(&self, mut out: impl Write, top: usize) -> io::Result<()> {
+        let total: u64 = self.counts.iter().sum();
+        writeln!(out, "{} instructions executed", total)?;
+        let mut hottest: Vec<_> = (0..self.counts.len())
+            .filter(|&a| self.counts[a] > 0)
+            .collect();
+        hottest.sort_by_key(|&a| (std::cmp::Reverse(self.counts[a]), a));
+        writeln!(out, "hottest instructions:")?;
+        for &address in hottest.iter().take(top) {
+            writeln!(
+                out,
+                "  {:>4}  {:<16}  {:>12}  {:>5.1}%",
+                address,
+                self.instructions[address].to_string(),
+                self.counts[address],
+                percentage(self.counts[address], total)
+            )?;
+        }
+        let mut back_edges: Vec<_> = self.back_edges.iter().collect();
+        back_edges.sort_by_key(|&(&edge, &count)| (std::cmp::Reverse(count), edge));
+        writeln!(out, "back edges:")?;
+        for (&(from, to), &count) in back_edges.iter().take(top) {
+            writeln!(out, "  {:>4} -> {:<4}  {:>12}", from, to, count)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the log and writes the summary file of a tracer made by `Trace::create`.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        if let Some((out, _)) = &mut self.log {
+            out.flush()?;
+        }
+        if let Some(path) = &self.summary {
+            self.write_summary(BufWriter::new(File::create(path)?), 20)?;
+        }
+        Ok(())
+    }
+}
+
+fn percentage(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+fn write_record(
+    out: &mut impl Write,
+    format: LogFormat,
+    address: usize,
+    instruction: Instruction,
+    before: &[usize; 6],
+    after: &[usize; 6],
+) -> io::Result<()> {
+    match format {
+        LogFormat::Csv => {
+            let registers: Vec<_> = before.iter().chain(after).map(usize::to_string).collect();
+            writeln!(out, "{},{},{}", address, instruction, registers.join(","))
+        }
+        LogFormat::Binary => {
+            out.write_all(&(address as u32).to_le_bytes())?;
+            out.write_all(&[instruction.kind as u8])?;
+            for parameter in instruction.parameters {
+                out.write_all(&(parameter as u32).to_le_bytes())?;
+            }
+            for &register in before.iter().chain(after) {
+                out.write_all(&(register as u64).to_le_bytes())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Backend recording every instruction another backend runs.
+pub struct Traced<B = CPU> {
+    backend: B,
+    tracer: Tracer,
+}
+
+impl<B: Backend> Traced<B> {
+    pub fn new(backend: B, tracer: Tracer) -> Self {
+        Traced { backend, tracer }
+    }
+
+    pub fn tracer(&self) -> &Tracer {
+        &self.tracer
+    }
+
+    pub fn into_tracer(self) -> Tracer {
+        self.tracer
+    }
+}
+
+impl<B: Backend> Backend for Traced<B> {
+    fn step(&mut self) -> bool {
+        let before = self.backend.snapshot();
+        let running = self.backend.step();
+        if running {
+            self.tracer.record(&before, &self.backend.snapshot());
+        }
+        running
+    }
+
+    fn registers(&self) -> &[usize; 6] {
+        self.backend.registers()
+    }
+
+    fn registers_mut(&mut self) -> &mut [usize; 6] {
+        self.backend.registers_mut()
+    }
+
+    fn address(&self) -> usize {
+        self.backend.address()
+    }
+
+    fn ip_binding(&self) -> Option<usize> {
+        self.backend.ip_binding()
+    }
+
+    fn overflow(&self) -> Option<Overflow> {
+        self.backend.overflow()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LogFormat, Trace, Traced, Tracer};
+    use crate::cpu::{get_cpu, Backend};
+    use crate::lines;
+    use crate::runner::Part;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer whose content stays readable after the tracer is dropped.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const PROGRAM: &str = lines!(
+        "#ip 2"
+        "seti 3 0 1"
+        "addi 1 0 1"
+        "addi 0 1 0"
+        "gtrr 0 1 3"
+        "addr 3 2 2"
+        "seti 1 0 2"
+    );
+
+    #[test]
+    fn counts_instructions_and_back_edges() {
+        let cpu = get_cpu(PROGRAM, [0; 6]).unwrap();
+        let out = Shared::default();
+        let tracer = Tracer::new(&cpu)
+            .with_log(Box::new(out.clone()), LogFormat::Csv)
+            .unwrap();
+        let mut traced = Traced::new(cpu, tracer);
+        assert_eq!(traced.run(), Ok(4));
+        let tracer = traced.into_tracer();
+        assert_eq!(tracer.counts(), [1, 1, 4, 4, 4, 3]);
+        assert_eq!(tracer.back_edges().get(&(5, 2)), Some(&3));
+        let mut summary = Vec::new();
+        tracer.write_summary(&mut summary, 2).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        assert!(summary.starts_with("17 instructions executed\n"));
+        assert!(summary.contains("\n     5 -> 2                3\n"));
+        tracer.finish().unwrap();
+        let log = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<_> = log.lines().collect();
+        assert_eq!(lines.len(), 18);
+        assert!(lines[0].starts_with("ip,instruction,before_r0,"));
+        assert_eq!(lines[1], "0,seti 3 0 1,0,0,0,0,0,0,0,3,1,0,0,0");
+    }
+
+    #[test]
+    fn writes_fixed_size_binary_records() {
+        let cpu = get_cpu(PROGRAM, [0; 6]).unwrap();
+        let out = Shared::default();
+        let tracer = Tracer::new(&cpu)
+            .with_log(Box::new(out.clone()), LogFormat::Binary)
+            .unwrap();
+        let mut traced = Traced::new(cpu, tracer);
+        traced.run().unwrap();
+        traced.into_tracer().finish().unwrap();
+        let log = out.0.lock().unwrap().clone();
+        assert_eq!(log.len(), 17 * (4 + 1 + 12 + 96));
+        assert_eq!(log[..4], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn keeps_traces_of_named_inputs_apart() {
+        let directory = env::temp_dir().join(format!("aoc-traces-{}", std::process::id()));
+        let cpu = get_cpu(PROGRAM, [0; 6]).unwrap();
+        for input in &[None, Some("alice"), Some("bob")] {
+            let trace = Trace {
+                directory: directory.clone(),
+                format: LogFormat::Csv,
+                input: input.map(String::from),
+            };
+            let mut traced = Traced::new(cpu.clone(), trace.create(&cpu, 19, Part::Two).unwrap());
+            traced.run().unwrap();
+            traced.into_tracer().finish().unwrap();
+        }
+        let mut names: Vec<_> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "day19.alice.part2.csv",
+                "day19.alice.part2.summary.txt",
+                "day19.bob.part2.csv",
+                "day19.bob.part2.summary.txt",
+                "day19.part2.csv",
+                "day19.part2.summary.txt",
+            ]
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,41 +1,52 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use std::collections::HashSet;
+use std::error::Error;
 
-pub(super) const DAY1: Solution = Solution {
-    part1: |input| {
-        let mut sum = 0;
-        for line in input.lines() {
-            sum = line
-                .parse::<i64>()?
-                .checked_add(sum)
-                .ok_or("Integer overflow")?;
-        }
-        Ok(sum.to_string())
-    },
-    part2: |input| {
-        let lines = input
+pub(super) struct Day1;
+
+impl Puzzle for Day1 {
+    type Input<'a> = Vec<i64>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 1;
+    const TITLE: &'static str = "Chronal Calibration";
+
+    fn parse(input: &str) -> Result<Vec<i64>, Box<dyn Error>> {
+        Ok(input
             .lines()
             .map(str::parse)
-            .collect::<Result<Vec<i64>, _>>()?;
+            .collect::<Result<Vec<i64>, _>>()?)
+    }
+
+    fn part1(changes: &Vec<i64>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut sum = 0i64;
+        for change in changes {
+            sum = change.checked_add(sum).ok_or("Integer overflow")?;
+        }
+        Ok(sum.to_string())
+    }
+
+    fn part2(changes: &Vec<i64>, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let mut sum = 0;
         let mut found = HashSet::new();
-        for line in lines.iter().cycle() {
+        for change in changes.iter().cycle() {
             if !found.insert(sum) {
                 return Ok(sum.to_string());
             }
-            sum = line.checked_add(sum).ok_or("Integer overflow")?;
+            sum = change.checked_add(sum).ok_or("Integer overflow")?;
         }
         Err("Empty input".into())
-    },
-};
+    }
+}
 
 #[cfg(test)]
 mod test {
     use crate::test;
     test!(
-        DAY1.part1,
+        Day1.part1,
         fn overflow_fails() {
-            assert!((DAY1.part1)(&format!("{}\n{0}", i64::max_value())).is_err());
+            assert!(Day1::solve(Part::One, &format!("{}\n{0}", i64::MAX), &Default::default()).is_err());
         }
         empty: "" => 0,
         example1: lines!(1 -2 3 1) => 3,
@@ -43,18 +54,18 @@ mod test {
         example3: lines!(1 1 -2) => 0,
         example4: lines!(-1 -2 -3) => "-6",
         fn test_max_value() {
-            let input = format!("{}\n{}", i64::max_value(), i64::min_value());
-            assert_eq!((DAY1.part1)(&input).unwrap(), "-1");
+            let input = format!("{}\n{}", i64::MAX, i64::MIN);
+            assert_eq!(Day1::solve(Part::One, &input, &Default::default()).unwrap(), "-1");
         }
         input: 430,
     );
     test!(
-        DAY1.part2,
+        Day1.part2,
         fn overflow_fails() {
-            assert!((DAY1.part2)(&i64::max_value().to_string()).is_err());
+            assert!(Day1::solve(Part::Two, &i64::MAX.to_string(), &Default::default()).is_err());
         }
         fn empty_input_fails() {
-            assert!((DAY1.part2)("").is_err());
+            assert!(Day1::solve(Part::Two, "", &Default::default()).is_err());
         }
         example1: lines!(1 -2 3 1) => 2,
         example2: lines!(1 -1) => 0,
@@ -1,17 +1,36 @@
-use crate::Solution;
+use crate::error::{self, Span};
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use itertools::Itertools;
 use regex::Regex;
 use std::error::Error;
 
 const FONT_HEIGHT: i32 = 10;
 
-pub(super) const DAY10: Solution = Solution {
-    part1: |input| Ok(run_simulation(input)?.0),
-    part2: |input| Ok(run_simulation(input)?.1.to_string()),
-};
+pub(super) struct Day10;
 
-fn run_simulation(input: &str) -> Result<(String, usize), Box<dyn Error>> {
-    let mut particles = get_particles(input)?;
+impl Puzzle for Day10 {
+    type Input<'a> = Vec<Particle>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 10;
+    const TITLE: &'static str = "The Stars Align";
+
+    fn parse(input: &str) -> Result<Vec<Particle>, Box<dyn Error>> {
+        get_particles(input)
+    }
+
+    fn part1(particles: &Vec<Particle>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(run_simulation(particles)?.0)
+    }
+
+    fn part2(particles: &Vec<Particle>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(run_simulation(particles)?.1.to_string())
+    }
+}
+
+fn run_simulation(particles: &[Particle]) -> Result<(String, usize), Box<dyn Error>> {
+    let mut particles = particles.to_vec();
     for i in 0.. {
         let (min_y, max_y) = particles
             .iter()
@@ -59,7 +78,11 @@ fn get_particles(input: &str) -> Result<Vec<Particle>, Box<dyn Error>> {
     input
         .lines()
         .map(|line| {
-            let caps = regex.captures(line).ok_or("Match failure")?;
+            let caps = regex.captures(line).ok_or_else(|| error::Error::Syntax {
+                day: None,
+                span: Span::new(input, line),
+                expected: "position=<x, y> velocity=<x, y>".into(),
+            })?;
             Ok(Particle {
                 position_x: caps[1].parse().unwrap(),
                 position_y: caps[2].parse().unwrap(),
@@ -70,7 +93,8 @@ fn get_particles(input: &str) -> Result<Vec<Particle>, Box<dyn Error>> {
         .collect()
 }
 
-struct Particle {
+#[derive(Clone)]
+pub(super) struct Particle {
     position_x: i32,
     position_y: i32,
     velocity_x: i32,
@@ -88,7 +112,7 @@ impl Particle {
 mod test {
     use crate::test;
     test!(
-        DAY10.part2,
+        Day10.part2,
         input: 10_003,
     );
 }
@@ -1,9 +1,22 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use rayon::prelude::*;
+use std::error::Error;
 
-pub(super) const DAY11: Solution = Solution {
-    part1: |serial| {
-        let serial: i32 = serial.parse()?;
+pub(super) struct Day11;
+
+impl Puzzle for Day11 {
+    type Input<'a> = i32;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 11;
+    const TITLE: &'static str = "Chronal Charge";
+
+    fn parse(serial: &str) -> Result<i32, Box<dyn Error>> {
+        Ok(serial.parse()?)
+    }
+
+    fn part1(&serial: &i32, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let (x, y) = (1..=300 - 3 + 1)
             .flat_map(|x| (1..=300 - 3 + 1).map(move |y| (x, y)))
             .max_by_key(|&(x, y)| -> i32 {
@@ -14,9 +27,9 @@ pub(super) const DAY11: Solution = Solution {
             })
             .unwrap();
         Ok(format!("{},{}", x, y))
-    },
-    part2: |serial| {
-        let serial: i32 = serial.parse()?;
+    }
+
+    fn part2(&serial: &i32, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let (x, y, size, _) = (0..300 * 300)
             .into_par_iter()
             .map(|pos| {
@@ -43,8 +56,8 @@ pub(super) const DAY11: Solution = Solution {
             .max_by_key(|&(_, _, _, value)| value)
             .unwrap();
         Ok(format!("{},{},{}", x, y, size))
-    },
-};
+    }
+}
 
 fn get_power(serial: i32, x: i32, y: i32) -> i32 {
     let rack_id = x + 10;
@@ -55,13 +68,13 @@ fn get_power(serial: i32, x: i32, y: i32) -> i32 {
 mod test {
     use crate::test;
     test!(
-        DAY11.part1,
+        Day11.part1,
         example1: "18" => "33,45",
         example2: "42" => "21,61",
         input: "9798" => "44,37",
     );
     test!(
-        DAY11.part2,
+        Day11.part2,
         example1: "18" => "90,269,16",
         example2: "42" => "232,251,12",
         input: "9798" => "235,87,13",
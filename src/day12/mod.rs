@@ -1,22 +1,76 @@
-use crate::Solution;
+use crate::error::{self, Span};
+use crate::parameters::{Parameter, Parameters};
+use crate::puzzle::Puzzle;
 use arraymap::ArrayMap;
 use nom::types::CompleteStr;
 use nom::{alt, do_parse, many0, named, preceded, tag};
 use std::collections::HashSet;
 use std::error::Error;
 
-pub(crate) const DAY12: Solution = Solution {
-    part1: |input| Ok(run_simulation(input, 20)?.to_string()),
-    part2: |input| Ok(run_simulation(input, 50_000_000_000)?.to_string()),
-};
+pub(crate) struct Day12;
 
-fn run_simulation(input: &str, generations: i64) -> Result<i64, Box<dyn Error + '_>> {
-    let mut lines = input.lines();
-    let mut state = get_initial_state(lines.next().ok_or("Empty input")?)?;
-    if lines.next() != Some("") {
-        return Err("Expected empty line".into());
+impl Puzzle for Day12 {
+    type Input<'a> = Pots;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 12;
+    const TITLE: &'static str = "Subterranean Sustainability";
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter {
+            name: "part1_generations",
+            description: "number of generations simulated in part 1",
+            default: 20,
+        },
+        Parameter {
+            name: "part2_generations",
+            description: "number of generations simulated in part 2",
+            default: 50_000_000_000,
+        },
+    ];
+
+    fn parse(input: &str) -> Result<Pots, Box<dyn Error>> {
+        let mut lines = input.lines();
+        let initial_state = error::parse(input, lines.next().ok_or("Empty input")?, initial_state)?;
+        match lines.next() {
+            Some("") => {}
+            line => {
+                return Err(error::Error::Syntax {
+                    day: None,
+                    span: Span::new(input, line.unwrap_or(&input[input.len()..])),
+                    expected: "empty line".into(),
+                }
+                .into())
+            }
+        }
+        let rules = get_rules(input, lines)?;
+        Ok(Pots {
+            initial_state,
+            rules,
+        })
+    }
+
+    fn part1(pots: &Pots, parameters: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(run_simulation(pots, parameters.get("part1_generations")?).to_string())
+    }
+
+    fn part2(pots: &Pots, parameters: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(run_simulation(pots, parameters.get("part2_generations")?).to_string())
     }
-    let rules = get_rules(lines)?;
+}
+
+pub(crate) struct Pots {
+    initial_state: Vec<bool>,
+    rules: HashSet<[bool; 5]>,
+}
+
+fn run_simulation(
+    Pots {
+        initial_state,
+        rules,
+    }: &Pots,
+    generations: i64,
+) -> i64 {
+    let mut state = initial_state.clone();
     let mut beginning = 0;
     let mut previous_sum = 0;
     let mut previous_delta = 0;
@@ -39,13 +93,13 @@ fn run_simulation(input: &str, generations: i64) -> Result<i64, Box<dyn Error +
         if delta == previous_delta {
             delta_count += 1;
             if delta_count == 100 {
-                return Ok(get_state_sum(beginning, &state) + (generations - i - 1) * delta);
+                return get_state_sum(beginning, &state) + (generations - i - 1) * delta;
             }
         }
         previous_sum = sum;
         previous_delta = delta;
     }
-    Ok(get_state_sum(beginning, &state))
+    get_state_sum(beginning, &state)
 }
 
 fn trim_state(mut input: &[bool]) -> (usize, &[bool]) {
@@ -60,32 +114,29 @@ fn trim_state(mut input: &[bool]) -> (usize, &[bool]) {
     (counter, input)
 }
 
-fn get_initial_state(input: &str) -> Result<Vec<bool>, Box<dyn Error + '_>> {
-    match initial_state(CompleteStr(input))? {
-        (CompleteStr(""), line) => Ok(line),
-        _ => Err("Found text after a line".into()),
-    }
-}
-
 named!(
     initial_state(CompleteStr<'_>) -> Vec<bool>,
     preceded!(tag!("initial state: "), many0!(symbol))
 );
 
 fn get_rules<'a>(
+    input: &'a str,
     lines: impl Iterator<Item = &'a str>,
-) -> Result<HashSet<[bool; 5]>, Box<dyn Error + 'a>> {
+) -> Result<HashSet<[bool; 5]>, error::Error> {
     let mut output = HashSet::new();
     for line in lines {
-        match rule(CompleteStr(line))? {
-            (CompleteStr(""), ([false, false, false, false, false], true)) => {
-                return Err("Sequence of five dots cannot map to octothorpes".into());
+        match error::parse(input, line, rule)? {
+            ([false, false, false, false, false], true) => {
+                return Err(error::Error::Invalid {
+                    day: None,
+                    span: Span::new(input, line),
+                    message: "Sequence of five dots cannot map to octothorpes".into(),
+                });
             }
-            (CompleteStr(""), (input, true)) => {
-                output.insert(input);
+            (pattern, true) => {
+                output.insert(pattern);
             }
-            (CompleteStr(""), (_, false)) => {}
-            _ => return Err("Found text after a line".into()),
+            (_, false) => {}
         }
     }
     Ok(output)
@@ -126,7 +177,7 @@ fn get_state_sum(beginning: i64, state: &[bool]) -> i64 {
 mod test {
     use crate::test;
     test!(
-        DAY12.part1,
+        Day12.part1,
         example: lines!(
             "initial state: #..#.#..##......###...###"
             ""
@@ -148,7 +199,7 @@ mod test {
         input: 1623,
     );
     test!(
-        DAY12.part2,
+        Day12.part2,
         input: 1_600_000_000_401,
     );
 }
@@ -1,13 +1,25 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use num_complex::Complex;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
-pub(crate) const DAY13: Solution = Solution {
-    part1: |input| {
-        let map: Vec<_> = input.lines().map(|line| line.as_bytes()).collect();
-        let mut carts: Vec<_> = get_carts(&map).collect();
+pub(crate) struct Day13;
+
+impl Puzzle for Day13 {
+    type Input<'a> = Vec<&'a [u8]>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 13;
+    const TITLE: &'static str = "Mine Cart Madness";
+
+    fn parse(input: &str) -> Result<Vec<&[u8]>, Box<dyn Error>> {
+        Ok(input.lines().map(|line| line.as_bytes()).collect())
+    }
+
+    fn part1(map: &Vec<&[u8]>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut carts: Vec<_> = get_carts(map).collect();
         let mut positions: HashSet<_> = carts.iter().map(|c| c.position).collect();
         loop {
             carts.sort_by_key(|c| (c.position.im, c.position.re));
@@ -17,10 +29,10 @@ pub(crate) const DAY13: Solution = Solution {
                 }
             }
         }
-    },
-    part2: |input| {
-        let map: Vec<_> = input.lines().map(|line| line.as_bytes()).collect();
-        let mut carts: HashMap<_, _> = get_carts(&map).enumerate().collect();
+    }
+
+    fn part2(map: &Vec<&[u8]>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut carts: HashMap<_, _> = get_carts(map).enumerate().collect();
         let mut positions: HashMap<_, _> = carts.iter().map(|(&i, c)| (c.position, i)).collect();
         loop {
             let mut indexes: Vec<_> = carts.keys().cloned().collect();
@@ -44,8 +56,8 @@ pub(crate) const DAY13: Solution = Solution {
                 return Ok(format!("{},{}", re, im));
             }
         }
-    },
-};
+    }
+}
 
 fn get_carts<'a>(map: &'a [&[u8]]) -> impl Iterator<Item = Cart<'a>> {
     map.iter()
@@ -151,7 +163,7 @@ impl PositionSet for InsertMap<'_> {
 mod test {
     use crate::test;
     test!(
-        DAY13.part1,
+        Day13.part1,
         example1: lines!(
             "|"
             "v"
@@ -172,7 +184,7 @@ mod test {
         input: "111,13",
     );
     test!(
-        DAY13.part2,
+        Day13.part2,
         example: lines!(
             r"/>-<\  "
             r"|   |  "
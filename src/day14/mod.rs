@@ -1,4 +1,5 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use std::error::Error;
 
 macro_rules! do_while {
@@ -8,8 +9,20 @@ macro_rules! do_while {
     }
 }
 
-pub(crate) const DAY14: Solution = Solution {
-    part1: |input| {
+pub(crate) struct Day14;
+
+impl Puzzle for Day14 {
+    type Input<'a> = &'a str;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 14;
+    const TITLE: &'static str = "Chocolate Charts";
+
+    fn parse(input: &str) -> Result<&str, Box<dyn Error>> {
+        Ok(input.trim())
+    }
+
+    fn part1(input: &&str, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let input: usize = input.parse()?;
         let mut recipes = vec![3, 7];
         let mut elves = [0, 1];
@@ -26,8 +39,9 @@ pub(crate) const DAY14: Solution = Solution {
             }
         }
         Ok(recipes[input..][..10].iter().map(u8::to_string).collect())
-    },
-    part2: |input| {
+    }
+
+    fn part2(input: &&str, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let input = input
             .chars()
             .map(|c| Ok(c.to_digit(10).ok_or("Input has non-digit character")? as u8))
@@ -43,7 +57,7 @@ pub(crate) const DAY14: Solution = Solution {
             } }
             recipes[start_from..].reverse();
             for pos in start_from..recipes.len() {
-                if pos > input.len() && input == &recipes[pos - input.len()..pos] {
+                if pos > input.len() && input == recipes[pos - input.len()..pos] {
                     return Ok((pos - input.len()).to_string());
                 }
             }
@@ -51,14 +65,14 @@ pub(crate) const DAY14: Solution = Solution {
                 *elf = (*elf + usize::from(recipes[*elf]) + 1) % recipes.len();
             }
         }
-    },
-};
+    }
+}
 
 #[cfg(test)]
 mod test {
     use crate::test;
     test!(
-        DAY14.part1,
+        Day14.part1,
         example1: "9" => "5158916779",
         example2: "5" => "0124515891",
         example3: "18" => "9251071085",
@@ -66,7 +80,7 @@ mod test {
         input: "939601" => "5832873106",
     );
     test!(
-        DAY14.part2,
+        Day14.part2,
         example1: "51589" => 9,
         example2: "01245" => 5,
         example3: "92510" => 18,
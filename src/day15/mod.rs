@@ -1,105 +1,364 @@
-use crate::Solution;
-use enumset::{EnumSet, EnumSetType};
+use crate::parameters::{Parameter, Parameters};
+use crate::puzzle::{Answer, Puzzle};
+use crate::runner::Part;
+use rayon::prelude::*;
+use serde::Serialize;
 use std::collections::hash_map::{Entry, HashMap};
 use std::collections::{HashSet, VecDeque};
-use std::fmt::{self, Debug, Formatter};
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::mem;
 
-pub(crate) const DAY15: Solution = Solution {
-    part1: |input| {
+pub mod replay;
+
+pub(crate) struct Day15;
+
+impl Puzzle for Day15 {
+    type Input<'a> = &'a str;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 15;
+    const TITLE: &'static str = "Beverage Bandits";
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter {
+            name: "min_power",
+            description: "lowest elf attack power tried in part 2",
+            default: 4,
+        },
+        Parameter {
+            name: "max_power",
+            description: "highest elf attack power tried in part 2",
+            default: 200,
+        },
+    ];
+
+    fn parse(input: &str) -> Result<&str, Box<dyn Error>> {
+        Ok(input)
+    }
+
+    fn part1(input: &&str, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let mut game = Game::new(input, 3);
-        let mut rounds = 0;
-        while game.take_turns() {
-            rounds += 1;
-        }
-        Ok((rounds
-            * game
-                .players
-                .iter()
-                .map(|p| u32::from(p.hit_points))
-                .sum::<u32>())
-        .to_string())
-    },
-    part2: |input| {
-        'checking_powers: for power in 4..=200 {
-            let mut game = Game::new(input, power);
-            let mut rounds = 0;
-            while game.take_turns() {
-                rounds += 1;
-            }
-            if game
-                .players
-                .iter()
-                .any(|p| p.race == Race::Elf && p.hit_points == 0)
-            {
-                continue 'checking_powers;
+        let rounds = game.fight();
+        Ok((rounds * game.hit_points()).to_string())
+    }
+
+    fn part2(input: &&str, parameters: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(part2_victory(input, parameters)?.outcome().to_string())
+    }
+
+    /// The second part also gives the power, rounds and hit points of the victory it found.
+    fn answer(part: Part, input: &&str, parameters: &Parameters) -> Result<Answer, Box<dyn Error>> {
+        match part {
+            Part::One => Ok(Self::part1(input, parameters)?.into()),
+            Part::Two => {
+                let victory = part2_victory(input, parameters)?;
+                Ok(Answer::with_details(
+                    victory.outcome().to_string(),
+                    &victory,
+                )?)
             }
-            return Ok((rounds
-                * game
-                    .players
-                    .iter()
-                    .map(|p| u32::from(p.hit_points))
-                    .sum::<u32>())
-            .to_string());
         }
-        Err("Even an instant-kill elf won't stop the goblins".into())
-    },
-};
+    }
+}
+
+fn part2_victory(input: &str, parameters: &Parameters) -> Result<ElfVictory, Box<dyn Error>> {
+    let min_power = parameters.get("min_power")?;
+    let max_power = parameters.get("max_power")?;
+    if min_power > max_power {
+        return Err(format!(
+            "Parameter min_power has invalid value {}: above max_power {}",
+            min_power, max_power
+        )
+        .into());
+    }
+    weakest_elf_victory(input, min_power, max_power).ok_or_else(|| {
+        format!(
+            "Elves lose someone with every attack power from {} to {}",
+            min_power, max_power
+        )
+        .into()
+    })
+}
+
+/// Combat which elves win without losing anyone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ElfVictory {
+    pub power: u8,
+    /// Number of full rounds.
+    pub rounds: u32,
+    /// Hit points left to the elves.
+    pub hit_points: u32,
+}
+
+impl ElfVictory {
+    pub fn outcome(&self) -> u32 {
+        self.rounds * self.hit_points
+    }
+}
+
+/// Combat with elves attacking with given power, `None` if any elf dies.
+pub fn elf_victory(input: &str, power: u8) -> Option<ElfVictory> {
+    let config = GameConfig::puzzle(power).with_victory(Victory::FirstDeath('E'));
+    let mut game = Game::with_config(input, config);
+    let rounds = game.fight();
+    if game.stopped {
+        return None;
+    }
+    Some(ElfVictory {
+        power,
+        rounds,
+        hit_points: game.hit_points(),
+    })
+}
+
+/// Finds the lowest attack power with which no elf dies. More power doesn't always help, as a
+/// different goblin dying first changes how the combat goes, so every power is tried in order.
+/// Powers run in parallel and each combat ends with the first elf death, which keeps losing
+/// powers cheap.
+pub fn weakest_elf_victory(input: &str, min_power: u8, max_power: u8) -> Option<ElfVictory> {
+    (min_power..=max_power)
+        .into_par_iter()
+        .find_map_first(|power| elf_victory(input, power))
+}
+
+/// Side of a combat, whose units are drawn on the map with its glyph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Faction {
+    pub glyph: char,
+    /// Name of the whole faction, like Elves.
+    pub name: String,
+    pub hit_points: u16,
+    pub attack_power: u8,
+}
+
+impl Faction {
+    pub fn new(glyph: char, name: &str, hit_points: u16, attack_power: u8) -> Self {
+        Faction {
+            glyph,
+            name: name.to_string(),
+            hit_points,
+            attack_power,
+        }
+    }
+}
+
+/// When the combat ends, in addition to when a unit finds no enemies left at the start of its
+/// turn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Victory {
+    /// Once a single faction is left.
+    LastStanding,
+    /// Once a faction has no units left, checked at the start of every turn.
+    Elimination(char),
+    /// As soon as a unit of a faction dies, in the middle of a round.
+    FirstDeath(char),
+}
+
+/// Order deciding which unit goes first, which of equally weak enemies gets attacked and which
+/// of equally close squares a unit moves towards.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Top to bottom, then left to right.
+    ReadingOrder,
+    /// Left to right, then top to bottom.
+    ColumnOrder,
+}
 
-struct Game<'a> {
+impl TieBreak {
+    fn key(self, Position { x, y }: Position) -> (usize, usize) {
+        match self {
+            TieBreak::ReadingOrder => (y, x),
+            TieBreak::ColumnOrder => (x, y),
+        }
+    }
+
+    /// Squares next to a position, in this order.
+    fn nearby(self, Position { x, y }: Position) -> [Position; 4] {
+        let [first, second, third, fourth] = match self {
+            TieBreak::ReadingOrder => [(x, y - 1), (x - 1, y), (x + 1, y), (x, y + 1)],
+            TieBreak::ColumnOrder => [(x - 1, y), (x, y - 1), (x, y + 1), (x + 1, y)],
+        };
+        [first, second, third, fourth].map(|(x, y)| Position { x, y })
+    }
+}
+
+/// Rules of a combat.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameConfig {
+    pub factions: Vec<Faction>,
+    pub victory: Victory,
+    pub tie_break: TieBreak,
+}
+
+impl GameConfig {
+    /// Rules of the puzzle, with elves attacking with given power.
+    pub fn puzzle(elves_attack_power: u8) -> Self {
+        GameConfig {
+            factions: vec![
+                Faction::new('E', "Elves", 200, elves_attack_power),
+                Faction::new('G', "Goblins", 200, 3),
+            ],
+            victory: Victory::LastStanding,
+            tie_break: TieBreak::ReadingOrder,
+        }
+    }
+
+    /// Adds a faction, replacing the one with the same glyph.
+    pub fn with_faction(mut self, faction: Faction) -> Self {
+        self.factions.retain(|f| f.glyph != faction.glyph);
+        self.factions.push(faction);
+        self
+    }
+
+    pub fn with_victory(mut self, victory: Victory) -> Self {
+        self.victory = victory;
+        self
+    }
+
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn faction(&self, glyph: char) -> Option<&Faction> {
+        self.factions.iter().find(|f| f.glyph == glyph)
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::puzzle(3)
+    }
+}
+
+/// Combat between factions, elves and goblins unless configured otherwise.
+pub struct Game<'a> {
     board: Board<'a>,
     players: Vec<Player>,
+    config: GameConfig,
+    /// What units did since events were last taken, when they are recorded.
+    events: Option<Vec<Event>>,
+    /// Whether the combat ended in the middle of a turn, because of a death.
+    stopped: bool,
 }
 
 impl Game<'_> {
-    fn new(input: &str, elves_attack_power: u8) -> Game<'_> {
+    pub fn new(input: &str, elves_attack_power: u8) -> Game<'_> {
+        Game::with_config(input, GameConfig::puzzle(elves_attack_power))
+    }
+
+    /// Combat with units of every faction whose glyph is on the map.
+    pub fn with_config(input: &str, config: GameConfig) -> Game<'_> {
         let board: Vec<_> = input.lines().map(|x| x.as_bytes()).collect();
         let mut players = Vec::new();
         for (y, line) in board.iter().enumerate() {
-            for (x, tile) in line.iter().enumerate() {
-                let race = match tile {
-                    b'G' => Race::Goblin,
-                    b'E' => Race::Elf,
-                    _ => continue,
+            for (x, &tile) in line.iter().enumerate() {
+                let faction = match config.faction(char::from(tile)) {
+                    Some(faction) => faction,
+                    None => continue,
                 };
                 players.push(Player {
-                    race,
+                    id: players.len(),
+                    faction: faction.glyph,
                     position: Position { x, y },
-                    hit_points: 200,
-                    attack_power: if race == Race::Elf {
-                        elves_attack_power
-                    } else {
-                        3
-                    },
+                    hit_points: faction.hit_points,
+                    attack_power: faction.attack_power,
                 })
             }
         }
         Game {
-            board: Board { board },
+            board: Board {
+                board,
+                tie_break: config.tie_break,
+            },
             players,
+            config,
+            events: None,
+            stopped: false,
+        }
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Faction of every unit left standing, if there is only one.
+    pub fn winner(&self) -> Option<&Faction> {
+        let mut alive = self.players.iter().filter(|p| p.hit_points != 0);
+        let faction = alive.next()?.faction;
+        if alive.all(|p| p.faction == faction) {
+            self.config.faction(faction)
+        } else {
+            None
         }
     }
 
-    fn take_turns(&mut self) -> bool {
-        self.players.sort_by_key(|p| p.position);
+    /// Runs rounds until the combat ends, returning the number of full rounds.
+    pub fn fight(&mut self) -> u32 {
+        let mut rounds = 0;
+        while self.take_turns() {
+            rounds += 1;
+        }
+        rounds
+    }
+
+    /// Hit points of every unit left standing.
+    pub fn hit_points(&self) -> u32 {
+        self.players.iter().map(|p| u32::from(p.hit_points)).sum()
+    }
+
+    /// Records what units do, to be collected with [`Game::take_events`].
+    pub fn with_events(mut self) -> Self {
+        self.events = Some(Vec::new());
+        self
+    }
+
+    /// Events recorded since the last call.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        self.events.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    fn record(&mut self, event: Event) {
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+
+    /// Runs a single round, returning `false` when it ended early because the combat is over.
+    pub fn take_turns(&mut self) -> bool {
+        let tie_break = self.config.tie_break;
+        self.players.sort_by_key(|p| tie_break.key(p.position));
         let mut positions = self.get_positions();
         for player in 0..self.players.len() {
             if self.players[player].hit_points == 0 {
                 continue;
             }
-            let mut map = EnumSet::new();
-            for player in &self.players {
-                if player.hit_points != 0 {
-                    map.insert(player.race);
-                }
-            }
-            if map.len() != 2 {
+            if self.is_over() {
                 return false;
             }
             self.take_turn(&mut positions, player);
+            if self.stopped {
+                return false;
+            }
         }
         true
     }
 
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    fn is_over(&self) -> bool {
+        let mut alive = self.players.iter().filter(|p| p.hit_points != 0);
+        match self.config.victory {
+            Victory::Elimination(faction) if !alive.clone().any(|p| p.faction == faction) => true,
+            _ => match alive.next() {
+                Some(first) => alive.all(|p| p.faction == first.faction),
+                None => true,
+            },
+        }
+    }
+
     fn get_positions(&self) -> HashMap<Position, usize> {
         let mut map = HashMap::new();
         for (i, p) in self
@@ -122,8 +381,19 @@ impl Game<'_> {
             target = self.find_nearby_target(positions, player);
         }
         if let Some(target) = target {
+            let attacker = player.unit();
             let attack_power = player.attack_power;
             self.hurt(positions, target, attack_power);
+            let target = &self.players[target];
+            let (target, hit_points) = (target.unit(), target.hit_points);
+            self.record(Event::Attack {
+                unit: attacker,
+                target,
+                hit_points,
+            });
+            if hit_points == 0 {
+                self.record(Event::Death { unit: target });
+            }
         }
     }
 
@@ -132,12 +402,13 @@ impl Game<'_> {
         positions: &HashMap<Position, usize>,
         player: &Player,
     ) -> Option<usize> {
-        player
-            .position
-            .nearby()
-            .filter_map(|position| positions.get(&position))
+        self.config
+            .tie_break
+            .nearby(player.position)
+            .iter()
+            .filter_map(|position| positions.get(position))
             .cloned()
-            .filter(|&other_player| self.players[other_player].race != player.race)
+            .filter(|&other_player| self.players[other_player].faction != player.faction)
             .min_by_key(|&other_player| self.players[other_player].hit_points)
     }
 
@@ -150,7 +421,7 @@ impl Game<'_> {
         let positions = self
             .players
             .iter()
-            .filter(|p| p.race != player.race && p.hit_points != 0)
+            .filter(|p| p.faction != player.faction && p.hit_points != 0)
             .flat_map(|p| p.get_reachable_positions(&self.board, cached_positions))
             .collect();
         if let Some(new_position) =
@@ -162,7 +433,14 @@ impl Game<'_> {
                 Some(player_number)
             );
             cached_positions.insert(new_position, player_number);
+            let from = player.position;
+            let unit = player.unit();
             self.players[player_number].position = new_position;
+            self.record(Event::Move {
+                unit,
+                from,
+                to: new_position,
+            });
         }
     }
 
@@ -172,19 +450,26 @@ impl Game<'_> {
         other_player: usize,
         attack_power: u8,
     ) {
-        let mut other_player = &mut self.players[other_player];
-        other_player.hit_points = other_player.hit_points.saturating_sub(attack_power);
+        let other_player = &mut self.players[other_player];
+        other_player.hit_points = other_player.hit_points.saturating_sub(attack_power.into());
         if other_player.hit_points == 0 {
             positions.remove(&other_player.position);
+            if self.config.victory == Victory::FirstDeath(other_player.faction) {
+                self.stopped = true;
+            }
         }
     }
 }
 
-impl Debug for Game<'_> {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+impl Game<'_> {
+    /// Rows of the board, each with the hit points of the units on it.
+    fn rows(&self) -> Vec<(String, Vec<String>)> {
         let positions = self.get_positions();
-        f.debug_list()
-            .entries(self.board.board.iter().enumerate().map(|(y, line)| {
+        self.board
+            .board
+            .iter()
+            .enumerate()
+            .map(|(y, line)| {
                 let mut output = String::new();
                 let mut players = Vec::new();
                 for (x, &tile) in line.iter().enumerate() {
@@ -192,24 +477,47 @@ impl Debug for Game<'_> {
                         '#'
                     } else if let Some(&player) = positions.get(&Position { x, y }) {
                         let player = &self.players[player];
-                        let letter = match player.race {
-                            Race::Elf => 'E',
-                            Race::Goblin => 'G',
-                        };
-                        players.push(format!("{}({})", letter, player.hit_points));
-                        letter
+                        players.push(format!("{}({})", player.faction, player.hit_points));
+                        player.faction
                     } else {
                         '.'
                     });
                 }
-                format!("{}    {}", output, players.join(", "))
-            }))
+                (output, players)
+            })
+            .collect()
+    }
+}
+
+impl Debug for Game<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.rows()
+                    .into_iter()
+                    .map(|(row, players)| format!("{}    {}", row, players.join(", "))),
+            )
             .finish()
     }
 }
 
+/// Draws the board the way the puzzle does in its examples.
+impl Display for Game<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (row, players) in self.rows() {
+            if players.is_empty() {
+                writeln!(f, "{}", row)?;
+            } else {
+                writeln!(f, "{}   {}", row, players.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 struct Board<'a> {
     board: Vec<&'a [u8]>,
+    tie_break: TieBreak,
 }
 
 impl Board<'_> {
@@ -231,7 +539,7 @@ impl Board<'_> {
         let mut last_of_level = 1;
         while let Some(original_position) = to_scan.pop_front() {
             last_of_level -= 1;
-            for position in original_position.nearby() {
+            for position in self.tie_break.nearby(original_position) {
                 if self.is_passable(position, cached_positions) {
                     if let Entry::Vacant(vacant) = scanned.entry(position) {
                         vacant.insert(Some(original_position));
@@ -245,7 +553,11 @@ impl Board<'_> {
             if last_of_level == 0 {
                 if !found_targets.is_empty() {
                     let mut prefinal_position = None;
-                    let mut final_position = *found_targets.iter().min().unwrap();
+                    let tie_break = self.tie_break;
+                    let mut final_position = *found_targets
+                        .iter()
+                        .min_by_key(|&&p| tie_break.key(p))
+                        .unwrap();
                     while let Some(previous_position) = scanned[&final_position] {
                         prefinal_position = Some(final_position);
                         final_position = previous_position;
@@ -260,43 +572,109 @@ impl Board<'_> {
 }
 
 #[derive(Debug)]
-struct Player {
-    race: Race,
+pub struct Player {
+    id: usize,
+    faction: char,
     position: Position,
-    hit_points: u8,
+    hit_points: u16,
     attack_power: u8,
 }
 
 impl Player {
+    /// Number of the unit in reading order at the start of the combat.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Glyph of the faction of the unit.
+    pub fn faction(&self) -> char {
+        self.faction
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn hit_points(&self) -> u16 {
+        self.hit_points
+    }
+
+    pub fn attack_power(&self) -> u8 {
+        self.attack_power
+    }
+
+    pub fn unit(&self) -> Unit {
+        Unit {
+            id: self.id,
+            faction: self.faction,
+        }
+    }
+
     fn get_reachable_positions<'a>(
         &self,
         board: &'a Board<'_>,
         cached_positions: &'a HashMap<Position, usize>,
     ) -> impl Iterator<Item = Position> + 'a {
-        self.position
-            .nearby()
+        IntoIterator::into_iter(board.tie_break.nearby(self.position))
             .filter(move |&p| board.is_passable(p, cached_positions))
     }
 }
 
-#[derive(Debug, EnumSetType)]
-enum Race {
-    Elf,
-    Goblin,
+/// Unit as named in events, like G3 for the fourth unit in reading order, of faction G.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Unit {
+    pub id: usize,
+    pub faction: char,
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.faction, self.id)
+    }
+}
+
+/// Something a unit did during its turn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Move {
+        unit: Unit,
+        from: Position,
+        to: Position,
+    },
+    /// The target is left with these hit points.
+    Attack {
+        unit: Unit,
+        target: Unit,
+        hit_points: u16,
+    },
+    Death {
+        unit: Unit,
+    },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Move { unit, from, to } => write!(f, "{} moves from {} to {}", unit, from, to),
+            Event::Attack {
+                unit,
+                target,
+                hit_points,
+            } => write!(f, "{} attacks {}, leaving {} HP", unit, target, hit_points),
+            Event::Death { unit } => write!(f, "{} dies", unit),
+        }
+    }
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-struct Position {
-    y: usize,
-    x: usize,
+pub struct Position {
+    pub y: usize,
+    pub x: usize,
 }
 
-impl Position {
-    fn nearby(self) -> impl Iterator<Item = Position> {
-        let Position { x, y } = self;
-        vec![(x, y - 1), (x - 1, y), (x + 1, y), (x, y + 1)]
-            .into_iter()
-            .map(|(x, y)| Position { x, y })
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
     }
 }
 
@@ -304,7 +682,7 @@ impl Position {
 mod test {
     use crate::test;
     test!(
-        DAY15.part1,
+        Day15.part1,
         maze_world: lines!(
             "##########"
             "#G.......#"
@@ -327,8 +705,7 @@ mod test {
                 "#...G.#"
                 "#######"
             ), 3);
-            use crate::day15::Race::{Elf, Goblin};
-            assert_eq!(game.players.iter().map(|p| p.race).collect::<Vec<_>>(), [Goblin, Goblin, Elf, Elf, Goblin, Goblin, Goblin]);
+            assert_eq!(game.players.iter().map(|p| p.faction).collect::<String>(), "GGEEGGG");
             let hps = [9, 4, 1, 200, 3, 3, 2];
             for (player, &hp) in game.players.iter_mut().zip(&hps) {
                 player.hit_points = hp;
@@ -410,6 +787,68 @@ mod test {
             game.take_turn(&mut positions, 2);
             assert_eq!(game.players[2].position, Position { x: 2, y: 1 });
         }
+        fn configures_factions() {
+            use crate::day15::{Faction, Game, GameConfig, Victory};
+            let map = lines!(
+                "#######"
+                "#E.G.D#"
+                "#.....#"
+                "#######"
+            );
+            let config = GameConfig::puzzle(3)
+                .with_faction(Faction::new('E', "Elves", 250, 3))
+                .with_faction(Faction::new('D', "Dwarves", 100, 10));
+            let mut game = Game::with_config(map, config.clone());
+            let hit_points: Vec<_> = game.players.iter().map(|p| p.hit_points).collect();
+            assert_eq!(hit_points, [250, 200, 100]);
+            assert_eq!(game.fight(), 35);
+            assert_eq!(game.winner().map(|f| f.name.as_str()), Some("Elves"));
+            assert_eq!(game.hit_points(), 67);
+            let config = config.with_victory(Victory::Elimination('G'));
+            let mut game = Game::with_config(map, config);
+            assert_eq!(game.fight(), 16);
+            assert_eq!(game.winner(), None);
+            let survivors: String = game.players.iter().filter(|p| p.hit_points != 0).map(|p| p.faction).collect();
+            assert_eq!(survivors, "ED");
+        }
+        fn fights_with_hundreds_of_hit_points() {
+            use crate::day15::{Faction, Game, GameConfig};
+            let map = lines!(
+                "#######"
+                "#.G...#"
+                "#...EG#"
+                "#.#.#G#"
+                "#..G#E#"
+                "#.....#"
+                "#######"
+            );
+            let config = GameConfig::puzzle(3).with_faction(Faction::new('E', "Elves", 300, 3));
+            let mut game = Game::with_config(map, config);
+            assert!(game.to_string().contains("E(300), G(200)"), "{}", game);
+            let rounds = game.fight();
+            assert_eq!((rounds, game.hit_points()), (70, 489));
+            assert_eq!(game.winner().map(|f| f.name.as_str()), Some("Goblins"));
+        }
+        fn breaks_ties_by_column() {
+            use crate::day15::{Event, Game, GameConfig, TieBreak, Unit};
+            let map = lines!(
+                "#####"
+                "#.G.#"
+                "#GE.#"
+                "#####"
+            );
+            let attacked = |tie_break| {
+                let config = GameConfig::puzzle(3).with_tie_break(tie_break);
+                let mut game = Game::with_config(map, config).with_events();
+                game.take_turns();
+                game.take_events().into_iter().find_map(|event| match event {
+                    Event::Attack { unit: Unit { faction: 'E', .. }, target, .. } => Some(target.id),
+                    _ => None,
+                })
+            };
+            assert_eq!(attacked(TieBreak::ReadingOrder), Some(0));
+            assert_eq!(attacked(TieBreak::ColumnOrder), Some(1));
+        }
         example1: lines!(
             "#######"
             "#.G...#"
@@ -469,7 +908,37 @@ mod test {
         input: 222_831,
     );
     test!(
-        DAY15.part2,
+        Day15.part2,
+        fn reports_weakest_victory() {
+            use crate::day15::{elf_victory, weakest_elf_victory, ElfVictory};
+            use crate::parameters::Parameters;
+            let input = lines!(
+                "#######"
+                "#.G...#"
+                "#...EG#"
+                "#.#.#G#"
+                "#..G#E#"
+                "#.....#"
+                "#######"
+            );
+            let victory = ElfVictory {
+                power: 15,
+                rounds: 29,
+                hit_points: 172,
+            };
+            assert_eq!(weakest_elf_victory(input, 4, 200), Some(victory));
+            assert_eq!(weakest_elf_victory(input, 15, 15), Some(victory));
+            assert_eq!(weakest_elf_victory(input, 4, 14), None);
+            assert_eq!(elf_victory(input, 14), None);
+            let solve = |min_power, max_power| {
+                let overrides = [("min_power".into(), min_power), ("max_power".into(), max_power)];
+                let parameters = Parameters::new(Day15::PARAMETERS, &overrides).unwrap();
+                Day15::solve(Part::Two, input, &parameters).map_err(|e| e.to_string())
+            };
+            assert_eq!(solve(4, 10).unwrap_err(), "Elves lose someone with every attack power from 4 to 10");
+            assert_eq!(solve(20, 10).unwrap_err(), "Parameter min_power has invalid value 20: above max_power 10");
+            assert_eq!(solve(10, 20).unwrap(), "4988");
+        }
         example1: lines!(
             "#######"
             "#.G...#"
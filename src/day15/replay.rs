@@ -0,0 +1,196 @@
+use super::{Event, Game};
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+/// Plays a combat round by round, drawing the board after each one.
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    delay: Duration,
+    from_round: u32,
+    events: bool,
+    clear: bool,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits this long after drawing each round.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Only draws rounds starting with this one. Earlier rounds are still played.
+    pub fn from_round(mut self, round: u32) -> Self {
+        self.from_round = round;
+        self
+    }
+
+    /// Lists what every unit did below each round drawn.
+    pub fn with_events(mut self) -> Self {
+        self.events = true;
+        self
+    }
+
+    /// Clears the terminal before each round, so that the board stays in place.
+    pub fn clearing(mut self) -> Self {
+        self.clear = true;
+        self
+    }
+
+    /// Plays the whole combat, returning the number of full rounds.
+    pub fn play(&self, mut game: Game<'_>, mut out: impl Write) -> io::Result<u32> {
+        if self.events {
+            game = game.with_events();
+        }
+        if self.from_round == 0 {
+            self.draw(&mut out, "Initially:", &game, &[])?;
+        }
+        let mut rounds = 0;
+        let events = loop {
+            let full = game.take_turns();
+            let events = game.take_events();
+            if !full {
+                break events;
+            }
+            rounds += 1;
+            if rounds >= self.from_round {
+                let plural = if rounds == 1 { "" } else { "s" };
+                let heading = format!("After {} round{}:", rounds, plural);
+                self.draw(&mut out, &heading, &game, &events)?;
+            }
+        };
+        let heading = format!("Combat ends after {} full rounds", rounds);
+        self.draw(&mut out, &heading, &game, &events)?;
+        let hit_points = game.hit_points();
+        if let Some(winner) = game.winner() {
+            writeln!(
+                out,
+                "{} win with {} total hit points left",
+                winner.name, hit_points
+            )?;
+        }
+        writeln!(
+            out,
+            "Outcome: {} * {} = {}",
+            rounds,
+            hit_points,
+            rounds * hit_points
+        )?;
+        Ok(rounds)
+    }
+
+    fn draw(
+        &self,
+        out: &mut impl Write,
+        heading: &str,
+        game: &Game<'_>,
+        events: &[Event],
+    ) -> io::Result<()> {
+        if self.clear {
+            write!(out, "\x1b[2J\x1b[H")?;
+        }
+        writeln!(out, "{}", heading)?;
+        write!(out, "{}", game)?;
+        for event in events {
+            writeln!(out, "  {}", event)?;
+        }
+        writeln!(out)?;
+        out.flush()?;
+        if !self.delay.is_zero() {
+            thread::sleep(self.delay);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Replay;
+    use crate::day15::Game;
+    use crate::lines;
+
+    const EXAMPLE: &str = lines!(
+        "#######"
+        "#.G...#"
+        "#...EG#"
+        "#.#.#G#"
+        "#..G#E#"
+        "#.....#"
+        "#######"
+    );
+
+    fn replay(replay: Replay) -> (u32, String) {
+        let mut out = Vec::new();
+        let rounds = replay.play(Game::new(EXAMPLE, 3), &mut out).unwrap();
+        (rounds, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn draws_rounds_like_the_puzzle() {
+        let (rounds, out) = replay(Replay::new());
+        assert_eq!(rounds, 47);
+        let first = lines!(
+            "Initially:"
+            "#######"
+            "#.G...#   G(200)"
+            "#...EG#   E(200), G(200)"
+            "#.#.#G#   G(200)"
+            "#..G#E#   G(200), E(200)"
+            "#.....#"
+            "#######"
+            ""
+            "After 1 round:"
+            "#######"
+            "#..G..#   G(200)"
+            "#...EG#   E(197), G(197)"
+            "#.#G#G#   G(200), G(197)"
+            "#...#E#   E(197)"
+            "#.....#"
+            "#######"
+        );
+        assert!(out.starts_with(first), "{}", out);
+        let last = lines!(
+            "Combat ends after 47 full rounds"
+            "#######"
+            "#G....#   G(200)"
+            "#.G...#   G(131)"
+            "#.#.#G#   G(59)"
+            "#...#.#"
+            "#....G#   G(200)"
+            "#######"
+            ""
+            "Goblins win with 590 total hit points left"
+            "Outcome: 47 * 590 = 27730"
+        );
+        assert!(out.ends_with(last), "{}", out);
+    }
+
+    #[test]
+    fn lists_events_from_round() {
+        let (_, out) = replay(Replay::new().from_round(1).with_events());
+        let first = lines!(
+            "After 1 round:"
+            "#######"
+            "#..G..#   G(200)"
+            "#...EG#   E(197), G(197)"
+            "#.#G#G#   G(200), G(197)"
+            "#...#E#   E(197)"
+            "#.....#"
+            "#######"
+            "  G0 moves from 2,1 to 3,1"
+            "  E1 attacks G2, leaving 197 HP"
+            "  G2 attacks E1, leaving 197 HP"
+            "  G3 attacks E5, leaving 197 HP"
+            "  G4 moves from 3,4 to 3,3"
+            "  E5 attacks G3, leaving 197 HP"
+            ""
+            "After 2 rounds:"
+        );
+        assert!(out.starts_with(first), "{}", out);
+        assert!(out.contains("  E1 dies\n"), "{}", out);
+    }
+}
@@ -1,33 +1,47 @@
-use crate::Solution;
+use crate::cpu::{self, Arithmetic, InstructionKind};
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::types::CompleteStr;
 use nom::{do_parse, many0, map_res, named, tag, take_while};
 use std::collections::hash_map::{Entry, HashMap};
 use std::collections::HashSet;
 use std::error::Error;
 
-pub(crate) const DAY16: Solution = Solution {
-    part1: |input| {
-        let input = get_input(input)?;
+pub(crate) struct Day16;
+
+impl Puzzle for Day16 {
+    type Input<'a> = Input;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 16;
+    const TITLE: &'static str = "Chronal Classification";
+
+    fn parse(input: &str) -> Result<Input, Box<dyn Error>> {
+        Ok(error::parse(input, input, self::input)?)
+    }
+
+    fn part1(input: &Input, _: &Parameters) -> Result<String, Box<dyn Error>> {
         Ok(input
             .tests
             .iter()
             .filter(|t| t.possible_opcodes().count() >= 3)
             .count()
             .to_string())
-    },
-    part2: |input| {
-        let input = get_input(input)?;
-        let opcode_map = get_opcode_map(&input);
-        let mut registers = [0; 4];
-        for line in input.program {
-            registers[line[3]] = opcode_map[&line[0]](registers, line[1], line[2])
+    }
+
+    fn part2(input: &Input, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut cpu = cpu::load_numeric(&input.program, &get_opcode_map(input), [0; 4])?;
+        while cpu.step() {}
+        match cpu.overflow() {
+            Some(overflow) => Err(overflow.into()),
+            None => Ok(cpu.registers[0].to_string()),
         }
-        Ok(registers[0].to_string())
-    },
-};
+    }
+}
 
 #[derive(Debug)]
-struct Input {
+pub(crate) struct Input {
     tests: Vec<Test>,
     program: Vec<Line>,
 }
@@ -42,15 +56,6 @@ struct Test {
     after: Registers,
 }
 
-fn get_input(text: &str) -> Result<Input, Box<dyn Error + '_>> {
-    let (rest, result) = input(CompleteStr(text))?;
-    if rest.is_empty() {
-        Ok(result)
-    } else {
-        Err("Found text after input".into())
-    }
-}
-
 named!(
     input(CompleteStr<'_>) -> Input,
     do_parse!(
@@ -112,61 +117,23 @@ named!(
     map_res!(take_while!(|c| char::is_digit(c, 10)), |x: CompleteStr<'_>| x.parse())
 );
 
-const OPCODES: &[Opcode] = &[
-    // addr
-    |r, a, b| r[a] + r[b],
-    // addi
-    |r, a, b| r[a] + b,
-    // mulr
-    |r, a, b| r[a] * r[b],
-    // muli
-    |r, a, b| r[a] * b,
-    // banr
-    |r, a, b| r[a] & r[b],
-    // bani
-    |r, a, b| r[a] & b,
-    // borr
-    |r, a, b| r[a] | r[b],
-    // bori
-    |r, a, b| r[a] | b,
-    // setr
-    |r, a, _| r[a],
-    // seti
-    |_, a, _| a,
-    // gtir
-    |r, a, b| (a > r[b]).into(),
-    // gtri
-    |r, a, b| (r[a] > b).into(),
-    // gtrr
-    |r, a, b| (r[a] > r[b]).into(),
-    // eqir
-    |r, a, b| (a == r[b]).into(),
-    // eqri
-    |r, a, b| (r[a] == b).into(),
-    // eqrr
-    |r, a, b| (r[a] == r[b]).into(),
-];
-
-type Opcode = fn(Registers, usize, usize) -> usize;
-
 impl Test {
-    fn possible_opcodes(&self) -> impl Iterator<Item = usize> {
+    fn possible_opcodes(&self) -> impl Iterator<Item = InstructionKind> {
         let Test {
             before,
             line: [_, a, b, c],
             after,
         } = *self;
         let after = after[c];
-        OPCODES
+        InstructionKind::ALL
             .iter()
-            .enumerate()
-            .filter(move |(_, opcode)| opcode(before, a, b) == after)
-            .map(|(i, _)| i)
+            .copied()
+            .filter(move |kind| kind.apply(&before, a, b, Arithmetic::Checked) == Some(after))
     }
 }
 
-fn get_opcode_map(input: &Input) -> HashMap<usize, Opcode> {
-    let mut opcode_map: HashMap<usize, HashSet<usize>> = HashMap::new();
+fn get_opcode_map(input: &Input) -> HashMap<usize, InstructionKind> {
+    let mut opcode_map: HashMap<usize, HashSet<InstructionKind>> = HashMap::new();
     for test in &input.tests {
         let opcode = test.line[0];
         let possible_opcodes: HashSet<_> = test.possible_opcodes().collect();
@@ -205,7 +172,7 @@ fn get_opcode_map(input: &Input) -> HashMap<usize, Opcode> {
         .into_iter()
         .map(|(k, v)| {
             assert_eq!(v.len(), 1);
-            (k, OPCODES[v.into_iter().next().unwrap()])
+            (k, v.into_iter().next().unwrap())
         })
         .collect()
 }
@@ -215,7 +182,7 @@ mod test {
     use crate::test;
 
     test!(
-        DAY16.part1,
+        Day16.part1,
         fn test_identical_opcodes() {
             use crate::day16::Test;
             assert_eq!(
@@ -230,7 +197,7 @@ mod test {
         input: 544,
     );
     test!(
-        DAY16.part2,
+        Day16.part2,
         input: 600,
     );
 }
@@ -1,4 +1,6 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use itertools::Itertools;
 use nom::types::CompleteStr;
 use nom::{alt, do_parse, map_res, named, tag, take_while};
@@ -6,32 +8,46 @@ use std::collections::hash_map::{Entry, HashMap};
 use std::error::Error;
 use std::ops::RangeInclusive;
 
-pub(crate) const DAY17: Solution = Solution {
-    part1: |input| {
-        let mut board = Board::new(input)?;
+pub(crate) struct Day17;
+
+impl Puzzle for Day17 {
+    type Input<'a> = Board;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 17;
+    const TITLE: &'static str = "Reservoir Research";
+
+    fn parse(input: &str) -> Result<Board, Box<dyn Error>> {
+        Board::new(input)
+    }
+
+    fn part1(board: &Board, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut board = board.clone();
         board.run_water(500, 0);
         Ok(board
             .count_tiles(&[BlockState::SolidWater, BlockState::Flowing])
             .to_string())
-    },
-    part2: |input| {
-        let mut board = Board::new(input)?;
+    }
+
+    fn part2(board: &Board, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut board = board.clone();
         board.run_water(500, 0);
         Ok(board.count_tiles(&[BlockState::SolidWater]).to_string())
-    },
-};
+    }
+}
 
-struct Board {
+#[derive(Clone)]
+pub(crate) struct Board {
     map: HashMap<(i32, i32), BlockState>,
     min_y: i32,
     max_y: i32,
 }
 
 impl Board {
-    fn new(input: &str) -> Result<Self, Box<dyn Error + '_>> {
+    fn new(input: &str) -> Result<Self, Box<dyn Error>> {
         let mut map = HashMap::new();
-        for line in input.lines() {
-            match get_line(line)? {
+        for text in input.lines() {
+            match error::parse(input, text, line)? {
                 Line::XY(x, y) => map.extend(y.map(|y| ((x, y), BlockState::Solid))),
                 Line::YX(y, x) => map.extend(x.map(|x| ((x, y), BlockState::Solid))),
             }
@@ -128,13 +144,6 @@ enum BlockState {
     Flowing,
 }
 
-fn get_line(text: &str) -> Result<Line, Box<dyn Error + '_>> {
-    match line(CompleteStr(text))? {
-        (CompleteStr(""), line) => Ok(line),
-        _ => Err("Unexpected text after a line".into()),
-    }
-}
-
 enum Line {
     XY(i32, RangeInclusive<i32>),
     YX(i32, RangeInclusive<i32>),
@@ -175,7 +184,7 @@ named!(
 mod test {
     use crate::test;
     test!(
-        DAY17.part1,
+        Day17.part1,
         example: lines!(
             "x=495, y=2..7"
             "y=7, x=495..501"
@@ -195,7 +204,7 @@ mod test {
         input: 37858,
     );
     test!(
-        DAY17.part2,
+        Day17.part2,
         example: lines!(
             "x=495, y=2..7"
             "y=7, x=495..501"
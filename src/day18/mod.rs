@@ -1,11 +1,24 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use std::collections::HashMap;
 use std::error::Error;
 
-pub(crate) const DAY18: Solution = Solution {
-    part1: |input| {
-        let mut input = parse_input(input)?;
-        for _ in 0..10 {
+pub(crate) struct Day18;
+
+impl Puzzle for Day18 {
+    type Input<'a> = Board;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 18;
+    const TITLE: &'static str = "Settlers of The North Pole";
+
+    fn parse(input: &str) -> Result<Board, Box<dyn Error>> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Board, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut input = input.next_state();
+        for _ in 1..10 {
             input = input.next_state();
         }
         Ok((input.tiles.iter().filter(|&&t| t == Tile::Tree).count()
@@ -15,9 +28,10 @@ pub(crate) const DAY18: Solution = Solution {
                 .filter(|&&t| t == Tile::Lumberyard)
                 .count())
         .to_string())
-    },
-    part2: |input| {
-        let mut input = parse_input(input)?;
+    }
+
+    fn part2(input: &Board, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut input = input.clone();
         let mut states = HashMap::new();
         for i in 0..1_000_000_000 {
             if let Some(previous) = states.insert(input.tiles.clone(), i) {
@@ -36,8 +50,8 @@ pub(crate) const DAY18: Solution = Solution {
                 .filter(|&&t| t == Tile::Lumberyard)
                 .count())
         .to_string())
-    },
-};
+    }
+}
 
 fn parse_input(input: &str) -> Result<Board, Box<dyn Error>> {
     let mut tiles = Vec::new();
@@ -62,8 +76,8 @@ fn parse_input(input: &str) -> Result<Board, Box<dyn Error>> {
     Ok(Board { tiles, width })
 }
 
-#[derive(Debug)]
-struct Board {
+#[derive(Clone, Debug)]
+pub(crate) struct Board {
     tiles: Vec<Tile>,
     width: usize,
 }
@@ -150,7 +164,7 @@ enum Tile {
 mod test {
     use crate::test;
     test!(
-        DAY18.part1,
+        Day18.part1,
         example: lines!(
             ".#.#...|#."
             ".....#|##|"
@@ -166,7 +180,7 @@ mod test {
         input: 620_624,
     );
     test!(
-        DAY18.part2,
+        Day18.part2,
         input: 169_234,
     );
 }
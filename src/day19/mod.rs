@@ -1,41 +1,65 @@
-use crate::cpu::get_cpu;
-use crate::Solution;
-
-pub(crate) const DAY19: Solution = Solution {
-    part1: |input| {
-        let mut cpu = get_cpu(input, [0; 6])?;
-        while cpu.step() {}
-        Ok(cpu.registers[0].to_string())
-    },
-    part2: |input| {
-        let mut cpu = get_cpu(input, [1, 0, 0, 0, 0, 0])?;
-        // Determined by manually analyzing the assembly, it may not match other assemblies
-        // After 100 instructions, the max value should be set
-        for _ in 0..100 {
-            cpu.step();
+use crate::cpu::optimizer::Optimizer;
+use crate::cpu::tracer::{Trace, Traced};
+use crate::cpu::{get_cpu, Backend, CPU};
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
+use crate::runner::Part;
+use std::error::Error;
+
+pub(crate) struct Day19;
+
+impl Puzzle for Day19 {
+    type Input<'a> = CPU;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 19;
+    const TITLE: &'static str = "Go With The Flow";
+
+    fn parse(input: &str) -> Result<CPU, Box<dyn Error>> {
+        Ok(get_cpu(input, [0; 6])?)
+    }
+
+    fn part1(cpu: &CPU, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        run(cpu, Part::One, None)
+    }
+
+    fn part2(cpu: &CPU, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        run(cpu, Part::Two, None)
+    }
+
+    fn trace(
+        part: Part,
+        cpu: &CPU,
+        _: &Parameters,
+        trace: &Trace,
+    ) -> Result<String, Box<dyn Error>> {
+        run(cpu, part, Some(trace))
+    }
+}
+
+fn run(cpu: &CPU, part: Part, trace: Option<&Trace>) -> Result<String, Box<dyn Error>> {
+    let mut cpu = cpu.clone();
+    if part == Part::Two {
+        // The program sums the divisors of a large number, which the optimizer does directly.
+        cpu.registers = [1, 0, 0, 0, 0, 0];
+    }
+    match trace {
+        Some(trace) => {
+            let tracer = trace.create(&cpu, Day19::DAY, part)?;
+            let mut optimizer = Optimizer::with_backend(&cpu, Traced::new(cpu.clone(), tracer));
+            let answer = optimizer.run();
+            optimizer.into_backend().into_tracer().finish()?;
+            Ok(answer?.to_string())
         }
-        let &max = cpu.registers.iter().max().unwrap();
-        // Calculating the sum of integer factors
-        Ok((1..=(max as f64).sqrt() as usize)
-            .filter(|i| max % i == 0)
-            .map(|factor| {
-                let other_factor = max / factor;
-                if other_factor == factor {
-                    factor
-                } else {
-                    other_factor + factor
-                }
-            })
-            .sum::<usize>()
-            .to_string())
-    },
-};
+        None => Ok(Optimizer::new(cpu).run()?.to_string()),
+    }
+}
 
 #[cfg(test)]
 mod test {
     use crate::test;
     test!(
-        DAY19.part1,
+        Day19.part1,
         example: lines!(
             "#ip 0"
             "seti 5 0 1"
@@ -49,7 +73,7 @@ mod test {
         input: 1_922,
     );
     test!(
-        DAY19.part2,
+        Day19.part2,
         input: 22_302_144,
     );
 }
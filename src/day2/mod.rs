@@ -1,13 +1,27 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use std::collections::HashMap;
+use std::error::Error;
 
-pub(super) const DAY2: Solution = Solution {
-    part1: |input| {
+pub(super) struct Day2;
+
+impl Puzzle for Day2 {
+    type Input<'a> = Vec<&'a str>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 2;
+    const TITLE: &'static str = "Inventory Management System";
+
+    fn parse(input: &str) -> Result<Vec<&str>, Box<dyn Error>> {
+        Ok(input.lines().collect())
+    }
+
+    fn part1(ids: &Vec<&str>, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let mut twice = 0;
         let mut thrice = 0;
-        for line in input.lines() {
+        for id in ids {
             let mut counts = HashMap::new();
-            for c in line.chars() {
+            for c in id.chars() {
                 *counts.entry(c).or_insert(0) += 1;
             }
             if counts.values().any(|&v| v == 2) {
@@ -18,21 +32,21 @@ pub(super) const DAY2: Solution = Solution {
             }
         }
         Ok((twice * thrice).to_string())
-    },
-    part2: |input| {
-        let lines: Vec<&str> = input.lines().collect();
-        let pair = lines
+    }
+
+    fn part2(ids: &Vec<&str>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let pair = ids
             .iter()
             .enumerate()
-            .flat_map(|(i, &a)| lines[i + 1..].iter().map(move |&b| (a, b)))
+            .flat_map(|(i, &a)| ids[i + 1..].iter().map(move |&b| (a, b)))
             .find(|&pair| differs_by_exactly_one_character(pair))
             .ok_or("No common IDs found")?;
         Ok(zip_chars(pair)
             .filter(|(a, b)| a == b)
             .map(|(a, _)| a)
             .collect())
-    },
-};
+    }
+}
 
 fn differs_by_exactly_one_character(pair: (&str, &str)) -> bool {
     zip_chars(pair).filter(|(a, b)| a != b).count() == 1
@@ -46,18 +60,18 @@ fn zip_chars<'a>((a, b): (&'a str, &'a str)) -> impl Iterator<Item = (char, char
 mod test {
     use crate::test;
     test!(
-        DAY2.part1,
+        Day2.part1,
         empty: "" => 0,
         example: lines!("abcdef" "bababc" "abbcde" "abcccd" "aabcdd" "abcdee" "ababab") => 12,
         input: 7936,
     );
     test!(
-        DAY2.part2,
+        Day2.part2,
         fn empty_input_fails() {
-            assert!((DAY2.part2)("").is_err());
+            assert!(Day2::solve(Part::Two, "", &Default::default()).is_err());
         }
         fn input_with_no_common_ids_fails() {
-            assert!((DAY2.part2)(lines!("aa" "bb")).is_err());
+            assert!(Day2::solve(Part::Two, lines!("aa" "bb"), &Default::default()).is_err());
         }
         example: lines!("abcde" "fghij" "klmno" "pqrst" "fguij" "axcye" "wvxyz") => "fgij",
         input: "lnfqdscwjyteorambzuchrgpx",
@@ -1,31 +1,33 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use regex_syntax::hir::{Anchor, Group, Hir, HirKind, Literal};
 use regex_syntax::ParserBuilder;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 
-pub(crate) const DAY20: Solution = Solution {
-    part1: |input| {
-        let hir = ParserBuilder::new()
-            .nest_limit(1_000)
-            .build()
-            .parse(input)?;
-        let hir = get_regex_without_anchors(&hir)?;
-        let maze = Maze::from_regex_hir(hir)?;
+pub(crate) struct Day20;
+
+impl Puzzle for Day20 {
+    type Input<'a> = Maze;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 20;
+    const TITLE: &'static str = "A Regular Map";
+
+    fn parse(input: &str) -> Result<Maze, Box<dyn Error>> {
+        Maze::parse(input)
+    }
+
+    fn part1(maze: &Maze, _: &Parameters) -> Result<String, Box<dyn Error>> {
         Ok(maze.find_furthest_room()?.to_string())
-    },
-    part2: |input| {
-        let hir = ParserBuilder::new()
-            .nest_limit(1_000)
-            .build()
-            .parse(input)?;
-        let hir = get_regex_without_anchors(&hir)?;
-        let maze = Maze::from_regex_hir(hir)?;
+    }
+
+    fn part2(maze: &Maze, _: &Parameters) -> Result<String, Box<dyn Error>> {
         Ok(maze
             .count_rooms_with_shortest_path_through_at_least_1000_doors()?
             .to_string())
-    },
-};
+    }
+}
 
 fn get_regex_without_anchors(hir: &Hir) -> Result<&[Hir], Box<dyn Error>> {
     if let HirKind::Concat(hirs) = hir.kind() {
@@ -45,12 +47,23 @@ fn get_regex_without_anchors(hir: &Hir) -> Result<&[Hir], Box<dyn Error>> {
     }
 }
 
+type Callback<'a> = dyn FnMut(&mut Maze, (i32, i32)) -> Result<(), Box<dyn Error>> + 'a;
+
+/// Map of rooms built from a route regular expression.
 #[derive(Debug)]
-struct Maze {
+pub struct Maze {
     rooms: HashMap<(i32, i32), Room>,
 }
 
 impl Maze {
+    pub fn parse(input: &str) -> Result<Self, Box<dyn Error>> {
+        let hir = ParserBuilder::new()
+            .nest_limit(1_000)
+            .build()
+            .parse(input)?;
+        Self::from_regex_hir(get_regex_without_anchors(&hir)?)
+    }
+
     fn from_regex_hir(concat_hirs: &[Hir]) -> Result<Self, Box<dyn Error>> {
         let mut maze = Self {
             rooms: HashMap::new(),
@@ -87,7 +100,7 @@ impl Maze {
         &mut self,
         hir: &Hir,
         position: (i32, i32),
-        callback: &mut (dyn FnMut(&mut Self, (i32, i32)) -> Result<(), Box<dyn Error>>),
+        callback: &mut Callback<'_>,
     ) -> Result<(), Box<dyn Error>> {
         match hir.kind() {
             HirKind::Empty => callback(self, position),
@@ -108,7 +121,7 @@ impl Maze {
                 }
                 Ok(())
             }
-            HirKind::Concat(hirs) => self.put_hir_from_concat_iter(&hirs, position, callback),
+            HirKind::Concat(hirs) => self.put_hir_from_concat_iter(hirs, position, callback),
             _ => Err(format!("Unexpected HIR kind: {}", hir).into()),
         }
     }
@@ -139,7 +152,7 @@ impl Maze {
         Ok(new_position)
     }
 
-    fn find_furthest_room(&self) -> Result<usize, Box<dyn Error>> {
+    pub fn find_furthest_room(&self) -> Result<usize, Box<dyn Error>> {
         let mut to_check = VecDeque::new();
         to_check.push_back((0, 0));
         let mut visited = HashSet::new();
@@ -163,7 +176,7 @@ impl Maze {
         Err("Not all points are reachable".into())
     }
 
-    fn count_rooms_with_shortest_path_through_at_least_1000_doors(
+    pub fn count_rooms_with_shortest_path_through_at_least_1000_doors(
         &self,
     ) -> Result<usize, Box<dyn Error>> {
         let mut to_check = VecDeque::new();
@@ -205,7 +218,7 @@ struct Room {
 mod test {
     use crate::test;
     test!(
-        DAY20.part1,
+        Day20.part1,
         example1: "^WNE$" => 3,
         example2: "^ENWWW(NEEE|SSE(EE|N))$" => 10,
         example3: "^ENNWSWW(NEWS|)SSSEEN(WNSE|)EE(SWEN|)NNN$" => 18,
@@ -214,7 +227,7 @@ mod test {
         input: 3_739,
     );
     test!(
-        DAY20.part2,
+        Day20.part2,
         input: 8_409,
     );
 }
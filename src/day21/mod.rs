@@ -1,56 +1,131 @@
-use crate::cpu::{get_cpu, Instruction, InstructionKind};
-use crate::Solution;
+use crate::cpu::cycle::CycleDetector;
+use crate::cpu::optimizer::Optimizer;
+use crate::cpu::threaded::Threaded;
+use crate::cpu::tracer::{Trace, Traced};
+use crate::cpu::{get_cpu, Backend, Instruction, InstructionKind, CPU};
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
+use crate::runner::Part;
 use std::collections::HashSet;
+use std::error::Error;
 
-pub(crate) const DAY21: Solution = Solution {
-    part1: |input| {
-        let mut cpu = get_cpu(input, [0; 6])?;
-        while let Some(instruction) = cpu.current_instruction() {
-            let Instruction { kind, parameters } = instruction;
-            match (kind, parameters) {
-                (InstructionKind::Eqrr, [0, x, _]) | (InstructionKind::Eqrr, [x, 0, _]) => {
-                    return Ok(cpu.registers[x].to_string());
-                }
-                _ => {}
-            }
-            cpu.step();
+pub(crate) struct Day21;
+
+impl Puzzle for Day21 {
+    type Input<'a> = CPU;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 21;
+    const TITLE: &'static str = "Chronal Conversion";
+
+    fn parse(input: &str) -> Result<CPU, Box<dyn Error>> {
+        Ok(get_cpu(input, [0; 6])?)
+    }
+
+    fn part1(cpu: &CPU, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        run(cpu, Part::One, None)
+    }
+
+    fn part2(cpu: &CPU, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        run(cpu, Part::Two, None)
+    }
+
+    fn trace(
+        part: Part,
+        cpu: &CPU,
+        _: &Parameters,
+        trace: &Trace,
+    ) -> Result<String, Box<dyn Error>> {
+        run(cpu, part, Some(trace))
+    }
+}
+
+type Solver = fn(&mut dyn Backend, usize, usize) -> Result<String, Box<dyn Error>>;
+
+/// Runs a solver on the program, tracing it when asked to.
+fn run(cpu: &CPU, part: Part, trace: Option<&Trace>) -> Result<String, Box<dyn Error>> {
+    let solve: Solver = match part {
+        Part::One => first_compared,
+        Part::Two => last_new_compared,
+    };
+    let (address, x) = comparison(cpu).ok_or("Expected to find eq instruction")?;
+    match trace {
+        Some(trace) => {
+            let tracer = trace.create(cpu, Day21::DAY, part)?;
+            let mut optimizer = Optimizer::with_backend(cpu, Traced::new(cpu.clone(), tracer));
+            let answer = solve(&mut optimizer, address, x);
+            optimizer.into_backend().into_tracer().finish()?;
+            answer
+        }
+        None => solve(
+            &mut Optimizer::with_backend(cpu, Threaded::new(cpu)?),
+            address,
+            x,
+        ),
+    }
+}
+
+fn first_compared(
+    backend: &mut dyn Backend,
+    address: usize,
+    x: usize,
+) -> Result<String, Box<dyn Error>> {
+    while backend.address() != address {
+        if !backend.step() {
+            return match backend.overflow() {
+                Some(overflow) => Err(overflow.into()),
+                None => Err("Expected to find eq instruction".into()),
+            };
         }
-        Err("Expected to find eq instruction".into())
-    },
-    part2: |input| {
-        let mut cpu = get_cpu(input, [0; 6])?;
-        let mut last_result = None;
-        let mut found = HashSet::new();
-        while let Some(instruction) = cpu.current_instruction() {
-            let Instruction { kind, parameters } = instruction;
+    }
+    Ok(backend.registers()[x].to_string())
+}
+
+fn last_new_compared(
+    backend: &mut dyn Backend,
+    address: usize,
+    x: usize,
+) -> Result<String, Box<dyn Error>> {
+    let cycle = CycleDetector::new()
+        .at_address(address)
+        .watching(x)
+        .run(backend);
+    if let Some(overflow) = backend.overflow() {
+        return Err(overflow.into());
+    }
+    let cycle = cycle.ok_or("Program halted on 0")?;
+    // Values compared after the first time they were can't make the program run longer.
+    let mut found = HashSet::new();
+    let last_new = cycle.values.into_iter().filter(|&v| found.insert(v)).last();
+    Ok(last_new.ok_or("No results obtained")?.to_string())
+}
+
+/// Address of the instruction comparing register 0, and the other register compared.
+fn comparison(cpu: &CPU) -> Option<(usize, usize)> {
+    cpu.instructions()
+        .iter()
+        .enumerate()
+        .find_map(|(address, instruction)| {
+            let Instruction { kind, parameters } = *instruction;
             match (kind, parameters) {
                 (InstructionKind::Eqrr, [0, x, _]) | (InstructionKind::Eqrr, [x, 0, _]) => {
-                    let value = cpu.registers[x];
-                    if found.insert(value) {
-                        last_result = Some(value);
-                    } else {
-                        return Ok(last_result.ok_or("No results obtained")?.to_string());
-                    }
+                    Some((address, x))
                 }
-                _ => {}
+                _ => None,
             }
-            cpu.step();
-        }
-        Err("Program halted on 0".into())
-    },
-};
+        })
+}
 
 #[cfg(test)]
 mod test {
     use crate::test;
     test!(
-        DAY21.part1,
+        Day21.part1,
         input: 12_980_435,
     );
 
     test!(
-        DAY21.part2,
-        // Too slow
-        #[ignore] input: 14_431_711,
+        Day21.part2,
+        input: 14_431_711,
     );
 }
@@ -1,4 +1,6 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::types::CompleteStr;
 use nom::{do_parse, map_res, named, tag, take_while};
 use std::cmp::Reverse;
@@ -14,18 +16,31 @@ const ADJACENT_CALLBACKS: &[fn(u32, u32) -> Option<XyPair>] = &[
     |x, y| Some((x, y + 1)),
 ];
 
-pub(crate) const DAY22: Solution = Solution {
-    part1: |input| {
-        let mut map = Map::new(input)?;
+pub(crate) struct Day22;
+
+impl Puzzle for Day22 {
+    type Input<'a> = Map;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 22;
+    const TITLE: &'static str = "Mode Maze";
+
+    fn parse(input: &str) -> Result<Map, Box<dyn Error>> {
+        Ok(Map::new(input)?)
+    }
+
+    fn part1(map: &Map, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut map = map.clone();
         let MapPosition { x, y } = map.target;
         Ok((0..=x)
             .flat_map(|x| (0..=y).map(move |y| MapPosition { x, y }))
             .map(|position| map.get_corrosion(position) % 3)
             .sum::<u32>()
             .to_string())
-    },
-    part2: |input| {
-        let mut map = Map::new(input)?;
+    }
+
+    fn part2(map: &Map, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut map = map.clone();
         let mut heap = BinaryHeap::new();
         let mut checked = HashSet::new();
         heap.push((
@@ -52,7 +67,7 @@ pub(crate) const DAY22: Solution = Solution {
                     .iter()
                     .filter_map(|f| f(x, y))
                     .map(|(x, y)| MapPosition { x, y })
-                    .filter(|&p| map.get_corrosion(p) % 3 != blocked_tile.into())
+                    .filter(|&p| map.get_corrosion(p) % 3 != u32::from(blocked_tile))
                     .map(|map_position| {
                         (
                             Reverse(time + 1),
@@ -77,21 +92,19 @@ pub(crate) const DAY22: Solution = Solution {
             ));
         }
         panic!("Cannot reach max position");
-    },
-};
+    }
+}
 
-struct Map {
+#[derive(Clone)]
+pub(crate) struct Map {
     board: HashMap<MapPosition, u32>,
     target: MapPosition,
     depth: Depth,
 }
 
 impl Map {
-    fn new(text: &str) -> Result<Self, Box<dyn Error + '_>> {
-        match input(CompleteStr(text))? {
-            (CompleteStr(""), rest) => Ok(rest),
-            _ => Err("Unexpected text after input".into()),
-        }
+    fn new(text: &str) -> Result<Self, error::Error> {
+        error::parse(text, text, input)
     }
 
     fn get_corrosion(&mut self, position: MapPosition) -> u32 {
@@ -164,7 +177,7 @@ struct QueuePosition {
 mod test {
     use crate::test;
     test!(
-        DAY22.part1,
+        Day22.part1,
         example: lines!(
             "depth: 510"
             "target: 10,10"
@@ -172,7 +185,7 @@ mod test {
         input: 6_323,
     );
     test!(
-        DAY22.part2,
+        Day22.part2,
         example: lines!(
             "depth: 510"
             "target: 10,10"
@@ -1,15 +1,28 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::types::CompleteStr;
 use nom::{do_parse, map_res, named, tag, take_while};
 use std::error::Error;
 use z3::{Ast, Config, Context, Optimize};
 
-pub(crate) const DAY23: Solution = Solution {
-    part1: |input| {
-        let nanobots = input
+pub(crate) struct Day23;
+
+impl Puzzle for Day23 {
+    type Input<'a> = Vec<Nanobot>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 23;
+    const TITLE: &'static str = "Experimental Emergency Teleportation";
+
+    fn parse(input: &str) -> Result<Vec<Nanobot>, Box<dyn Error>> {
+        Ok(input
             .lines()
-            .map(get_nanobot)
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|line| error::parse(input, line, nanobot))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(nanobots: &Vec<Nanobot>, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let Nanobot { radius, position } = nanobots
             .iter()
             .max_by_key(|n| n.radius)
@@ -19,18 +32,19 @@ pub(crate) const DAY23: Solution = Solution {
             .filter(|n| *radius >= position.distance_to(&n.position))
             .count()
             .to_string())
-    },
-    part2: |input| {
+    }
+
+    fn part2(nanobots: &Vec<Nanobot>, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let ctx = Context::new(&Config::new());
         let zx = ctx.named_int_const("x");
         let zy = ctx.named_int_const("y");
         let zz = ctx.named_int_const("z");
         let mut in_ranges = Ast::from_i64(&ctx, 0);
-        for line in input.lines() {
-            let Nanobot {
-                radius,
-                position: Position { x, y, z },
-            } = get_nanobot(line)?;
+        for &Nanobot {
+            radius,
+            position: Position { x, y, z },
+        } in nanobots
+        {
             in_ranges = zabssub(&ctx, &zx, x)
                 .add(&[&zabssub(&ctx, &zy, y), &zabssub(&ctx, &zz, z)])
                 .le(&Ast::from_i64(&ctx, radius.into()))
@@ -50,13 +64,6 @@ pub(crate) const DAY23: Solution = Solution {
             .ok_or("Variable not obtainable as i64")?
             .abs();
         Ok(sum.to_string())
-    },
-};
-
-fn get_nanobot(line: &str) -> Result<Nanobot, Box<dyn Error + '_>> {
-    match nanobot(CompleteStr(line))? {
-        (CompleteStr(""), nanobot) => Ok(nanobot),
-        _ => Err("Found text after nanobot".into()),
     }
 }
 
@@ -87,7 +94,7 @@ named!(
 );
 
 #[derive(Debug)]
-struct Nanobot {
+pub(crate) struct Nanobot {
     position: Position,
     radius: i32,
 }
@@ -110,7 +117,7 @@ impl Position {
 }
 
 fn zabssub<'ctx>(ctx: &'ctx Context, zv: &Ast<'ctx>, v: i32) -> Ast<'ctx> {
-    zabs(&ctx, &zv.sub(&[&Ast::from_i64(&ctx, v.into())]))
+    zabs(ctx, &zv.sub(&[&Ast::from_i64(ctx, v.into())]))
 }
 
 fn zabs<'ctx>(ctx: &'ctx Context, v: &Ast<'ctx>) -> Ast<'ctx> {
@@ -121,7 +128,7 @@ fn zabs<'ctx>(ctx: &'ctx Context, v: &Ast<'ctx>) -> Ast<'ctx> {
 mod test {
     use crate::test;
     test!(
-        DAY23.part1,
+        Day23.part1,
         example: lines!(
             "pos=<0,0,0>, r=4"
             "pos=<1,0,0>, r=1"
@@ -136,7 +143,7 @@ mod test {
         input: 253,
     );
     test!(
-        DAY23.part2,
+        Day23.part2,
         example: lines!(
             "pos=<10,12,12>, r=2"
             "pos=<12,14,12>, r=2"
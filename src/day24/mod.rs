@@ -1,4 +1,6 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::{
     alpha, alt, char, delimited, do_parse, many0, map_res, named, opt, preceded,
     separated_nonempty_list, tag, take_while, terminated, types::CompleteStr,
@@ -7,9 +9,21 @@ use std::cmp::Reverse;
 use std::collections::HashSet;
 use std::error::Error;
 
-pub(crate) const DAY24: Solution = Solution {
-    part1: |input| {
-        let mut sides = get_sides(input)?;
+pub(crate) struct Day24;
+
+impl Puzzle for Day24 {
+    type Input<'a> = [Vec<Army<'a>>; 2];
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 24;
+    const TITLE: &'static str = "Immune System Simulator 20XX";
+
+    fn parse(input: &str) -> Result<[Vec<Army<'_>>; 2], Box<dyn Error>> {
+        Ok(error::parse(input, input, sides)?)
+    }
+
+    fn part1(sides: &[Vec<Army<'_>>; 2], _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut sides = sides.clone();
         while run_simulation(&mut sides) {}
         for &(a, b) in &[(0, 1), (1, 0)] {
             if sides[a].iter().all(|a| a.units == 0) {
@@ -17,9 +31,9 @@ pub(crate) const DAY24: Solution = Solution {
             }
         }
         Err("Neither side won".into())
-    },
-    part2: |input| {
-        let sides = get_sides(input)?;
+    }
+
+    fn part2(sides: &[Vec<Army<'_>>; 2], _: &Parameters) -> Result<String, Box<dyn Error>> {
         for boost in 0.. {
             let mut sides = sides.clone();
             for unit in &mut sides[0] {
@@ -31,13 +45,6 @@ pub(crate) const DAY24: Solution = Solution {
             }
         }
         unreachable!()
-    },
-};
-
-fn get_sides(input: &str) -> Result<[Vec<Army<'_>>; 2], Box<dyn Error + '_>> {
-    match sides(CompleteStr(input))? {
-        (CompleteStr(""), sides) => Ok(sides),
-        _ => Err("Unexpected text after match".into()),
     }
 }
 
@@ -58,7 +65,7 @@ named!(
 );
 
 #[derive(Clone, Debug)]
-struct Army<'a> {
+pub(crate) struct Army<'a> {
     units: u32,
     hit_points: u32,
     weaknesses: HashSet<&'a str>,
@@ -69,7 +76,7 @@ struct Army<'a> {
 }
 
 named!(
-    army(CompleteStr<'_>) -> Army,
+    army(CompleteStr<'_>) -> Army<'_>,
     do_parse!(
         units: integer
             >> tag!(" units each with ")
@@ -203,7 +210,7 @@ fn run_simulation(sides: &mut [Vec<Army<'_>>; 2]) -> bool {
 mod test {
     use crate::test;
     test!(
-        DAY24.part1,
+        Day24.part1,
         example: lines!(
             "Immune System:"
             "17 units each with 5390 hit points (weak to radiation, bludgeoning) with an attack that does 4507 fire damage at initiative 2"
@@ -216,7 +223,7 @@ mod test {
         input: 20_340,
     );
     test!(
-        DAY24.part2,
+        Day24.part2,
         example: lines!(
             "Immune System:"
             "17 units each with 5390 hit points (weak to radiation, bludgeoning) with an attack that does 4507 fire damage at initiative 2"
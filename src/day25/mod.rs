@@ -1,12 +1,28 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::{char, do_parse, map_res, named, take_while, types::CompleteStr};
 use std::error::Error;
 
-pub(crate) const DAY25: Solution = Solution {
-    part1: |input| {
+pub(crate) struct Day25;
+
+impl Puzzle for Day25 {
+    type Input<'a> = Vec<Position>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 25;
+    const TITLE: &'static str = "Four-Dimensional Adventure";
+
+    fn parse(input: &str) -> Result<Vec<Position>, Box<dyn Error>> {
+        Ok(input
+            .lines()
+            .map(|line| error::parse(input, line, position))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(positions: &Vec<Position>, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let mut constellations: Vec<Vec<Position>> = Vec::new();
-        for line in input.lines() {
-            let new_position = get_position(line)?;
+        for &new_position in positions {
             let mut constellation = vec![new_position];
             for i in (0..constellations.len()).rev() {
                 for position in &constellations[i] {
@@ -19,19 +35,15 @@ pub(crate) const DAY25: Solution = Solution {
             constellations.push(constellation);
         }
         Ok(constellations.len().to_string())
-    },
-    part2: |_| Ok(String::from("Trigger the Underflow")),
-};
+    }
 
-fn get_position(line: &str) -> Result<Position, Box<dyn Error + '_>> {
-    match position(CompleteStr(line))? {
-        (CompleteStr(""), position) => Ok(position),
-        _ => Err("Found text after constellation".into()),
+    fn part2(_: &Vec<Position>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(String::from("Trigger the Underflow"))
     }
 }
 
 #[derive(Copy, Clone)]
-struct Position([i8; 4]);
+pub(crate) struct Position([i8; 4]);
 
 impl Position {
     fn distance_to(self, other: Self) -> i32 {
@@ -69,7 +81,7 @@ named!(
 mod test {
     use crate::test;
     test!(
-        DAY25.part1,
+        Day25.part1,
         example1: lines!(
             "0,0,0,0"
             "3,0,0,0"
@@ -1,51 +1,57 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::types::CompleteStr;
 use nom::{do_parse, map_res, named, tag, take_while1};
 use std::collections::HashMap;
 use std::error::Error;
 
-pub(super) const DAY3: Solution = Solution {
-    part1: |input| {
-        Ok(get_claim_table(input)?
+pub(super) struct Day3;
+
+impl Puzzle for Day3 {
+    type Input<'a> = Vec<Claim>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 3;
+    const TITLE: &'static str = "No Matter How You Slice It";
+
+    fn parse(input: &str) -> Result<Vec<Claim>, Box<dyn Error>> {
+        Ok(input
+            .lines()
+            .map(|line| error::parse(input, line, claim))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(claims: &Vec<Claim>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(get_claim_table(claims)
             .values()
             .filter(|&&s| s == ClaimState::More)
             .count()
             .to_string())
-    },
-    part2: |input| {
-        let claim_table = get_claim_table(input)?;
-        for claim in get_claims(input) {
-            let claim = claim?;
-            if get_squares(&claim).all(|square| claim_table[&square] == ClaimState::Once) {
+    }
+
+    fn part2(claims: &Vec<Claim>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let claim_table = get_claim_table(claims);
+        for claim in claims {
+            if get_squares(claim).all(|square| claim_table[&square] == ClaimState::Once) {
                 return Ok(claim.num.to_string());
             }
         }
         Err("No non-overlapping claims".into())
-    },
-};
+    }
+}
 
-fn get_claim_table(input: &str) -> Result<HashMap<(u16, u16), ClaimState>, Box<dyn Error + '_>> {
+fn get_claim_table(claims: &[Claim]) -> HashMap<(u16, u16), ClaimState> {
     let mut claimed = HashMap::new();
-    for claim in get_claims(input) {
-        for square in get_squares(&claim?) {
+    for claim in claims {
+        for square in get_squares(claim) {
             claimed
                 .entry(square)
                 .and_modify(|s| *s = ClaimState::More)
                 .or_insert(ClaimState::Once);
         }
     }
-    Ok(claimed)
-}
-
-fn get_claims(input: &str) -> impl Iterator<Item = Result<Claim, Box<dyn Error + '_>>> + '_ {
-    input.lines().map(|line| {
-        let (rest, claim) = claim(CompleteStr(line))?;
-        if rest.is_empty() {
-            Ok(claim)
-        } else {
-            Err("Unexpected additional text after a claim".into())
-        }
-    })
+    claimed
 }
 
 fn get_squares(
@@ -60,7 +66,7 @@ fn get_squares(
     (0..area_x).flat_map(move |x| (0..area_y).map(move |y| (position_x + x, position_y + y)))
 }
 
-struct Claim {
+pub(super) struct Claim {
     num: u16,
     position_x: u16,
     position_y: u16,
@@ -108,14 +114,14 @@ enum ClaimState {
 mod test {
     use crate::test;
     test!(
-        DAY3.part1,
+        Day3.part1,
         empty: "" => 0,
         example1: "#123 @ 3,2: 5x4" => 0,
         example2: lines!("#1 @ 1,3: 4x4" "#2 @ 3,1: 4x4" "#3 @ 5,5: 2x2") => 4,
         input: 124850,
     );
     test!(
-        DAY3.part2,
+        Day3.part2,
         example1: "#123 @ 3,2: 5x4" => 123,
         example2: lines!("#1 @ 1,3: 4x4" "#2 @ 3,1: 4x4" "#3 @ 5,5: 2x2") => 3,
         input: 1097,
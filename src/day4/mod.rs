@@ -1,20 +1,48 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::types::CompleteStr;
 use nom::{alt, delimited, do_parse, map_res, named, tag, take_while1};
 use std::collections::HashMap;
 use std::error::Error;
 use std::hash::Hash;
 use std::ops::Range;
-use std::vec;
 
-pub(super) const DAY4: Solution = Solution {
-    part1: |input| {
+pub(super) struct Day4;
+
+impl Puzzle for Day4 {
+    type Input<'a> = Vec<SleepRange>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 4;
+    const TITLE: &'static str = "Repose Record";
+
+    fn parse(input: &str) -> Result<Vec<SleepRange>, Box<dyn Error>> {
+        let mut lines: Vec<_> = input.lines().collect();
+        lines.sort_unstable();
+        let mut sleep_ranges = Vec::new();
+        let mut current_guard = None;
+        let mut asleep_start_time = None;
+        for line in lines {
+            let Line { minute, action } = error::parse(input, line, action_line)?;
+            match action {
+                Action::BeginsShift { guard } => current_guard = Some(guard),
+                Action::FallsAsleep => asleep_start_time = Some(minute),
+                Action::WakesUp => sleep_ranges.push(SleepRange {
+                    guard: current_guard.ok_or("No guard on shift")?,
+                    minutes: asleep_start_time.ok_or("Guard didn't sleep")?..minute,
+                }),
+            }
+        }
+        Ok(sleep_ranges)
+    }
+
+    fn part1(sleep_ranges: &Vec<SleepRange>, _: &Parameters) -> Result<String, Box<dyn Error>> {
         let mut total_asleep_times = HashMap::new();
         let mut asleep_times = HashMap::new();
-        let mut parser = LineParser::new(input);
-        while let Some(SleepRange { guard, minutes }) = parser.next_sleep_range()? {
+        for SleepRange { guard, minutes } in sleep_ranges {
             *total_asleep_times.entry(guard).or_insert(0) += minutes.len();
-            for minute in minutes {
+            for minute in minutes.clone() {
                 *asleep_times
                     .entry(guard)
                     .or_insert_with(HashMap::new)
@@ -24,68 +52,18 @@ pub(super) const DAY4: Solution = Solution {
         }
         let worst_guard = find_max_value(&total_asleep_times).ok_or("No guards")?;
         let minute = find_max_value(&asleep_times[worst_guard]).unwrap();
-        Ok((worst_guard * minute).to_string())
-    },
-    part2: |input| {
-        let mut asleep_times = HashMap::new();
-        let mut parser = LineParser::new(input);
-        while let Some(SleepRange { guard, minutes }) = parser.next_sleep_range()? {
-            for minute in minutes {
-                *asleep_times.entry((guard, minute)).or_insert(0) += 1;
-            }
-        }
-        let (minute, guard) = find_max_value(&asleep_times).ok_or("No guards")?;
-        Ok((minute * guard).to_string())
-    },
-};
-
-struct LineParser<'a> {
-    iterator: vec::IntoIter<&'a str>,
-    current_guard: Option<u32>,
-    asleep_start_time: Option<u32>,
-}
-
-impl<'a> LineParser<'a> {
-    fn new(input: &str) -> LineParser<'_> {
-        LineParser {
-            iterator: get_sorted_lines_iter(input),
-            current_guard: None,
-            asleep_start_time: None,
-        }
+        Ok((*worst_guard * minute).to_string())
     }
 
-    fn next_sleep_range(&mut self) -> Result<Option<SleepRange>, Box<dyn Error + 'a>> {
-        for line in &mut self.iterator {
-            let Line { minute, action } = get_action_line(line)?;
-            match action {
-                Action::BeginsShift { guard } => self.current_guard = Some(guard),
-                Action::FallsAsleep => self.asleep_start_time = Some(minute),
-                Action::WakesUp => {
-                    let current_guard = self.current_guard.ok_or("No guard on shift")?;
-                    let current_asleep_time = self.asleep_start_time.ok_or("Guard didn't sleep")?;
-                    return Ok(Some(SleepRange {
-                        guard: current_guard,
-                        minutes: current_asleep_time..minute,
-                    }));
-                }
+    fn part2(sleep_ranges: &Vec<SleepRange>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut asleep_times = HashMap::new();
+        for SleepRange { guard, minutes } in sleep_ranges {
+            for minute in minutes.clone() {
+                *asleep_times.entry((guard, minute)).or_insert(0) += 1;
             }
         }
-        Ok(None)
-    }
-}
-
-fn get_sorted_lines_iter(input: &str) -> vec::IntoIter<&str> {
-    let mut lines: Vec<_> = input.lines().collect();
-    lines.sort_unstable();
-    lines.into_iter()
-}
-
-fn get_action_line(line: &str) -> Result<Line, Box<dyn Error + '_>> {
-    let (rest, action_line) = action_line(CompleteStr(line))?;
-    if rest.is_empty() {
-        Ok(action_line)
-    } else {
-        Err("Unexpected additional text after an action line".into())
+        let (guard, minute) = find_max_value(&asleep_times).ok_or("No guards")?;
+        Ok((*guard * minute).to_string())
     }
 }
 
@@ -135,7 +113,7 @@ named!(
     )
 );
 
-struct SleepRange {
+pub(super) struct SleepRange {
     guard: u32,
     minutes: Range<u32>,
 }
@@ -152,7 +130,7 @@ where
 mod test {
     use crate::test;
     test!(
-        DAY4.part1,
+        Day4.part1,
         example: lines!(
             "[1518-11-01 00:00] Guard #10 begins shift"
             "[1518-11-01 00:05] falls asleep"
@@ -175,7 +153,7 @@ mod test {
         input: 87681,
     );
     test!(
-        DAY4.part2,
+        Day4.part2,
         example: lines!(
             "[1518-11-01 00:00] Guard #10 begins shift"
             "[1518-11-01 00:05] falls asleep"
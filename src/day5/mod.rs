@@ -1,16 +1,32 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use std::collections::HashSet;
+use std::error::Error;
 
-pub(super) const DAY5: Solution = Solution {
-    part1: |input| Ok(get_queue_len(input.trim().chars()).to_string()),
-    part2: |input| {
-        let input = input.trim();
-        let letters: HashSet<char> = input.chars().filter(|c| c.is_ascii_lowercase()).collect();
+pub(super) struct Day5;
+
+impl Puzzle for Day5 {
+    type Input<'a> = &'a str;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 5;
+    const TITLE: &'static str = "Alchemical Reduction";
+
+    fn parse(input: &str) -> Result<&str, Box<dyn Error>> {
+        Ok(input.trim())
+    }
+
+    fn part1(polymer: &&str, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(get_queue_len(polymer.chars()).to_string())
+    }
+
+    fn part2(polymer: &&str, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let letters: HashSet<char> = polymer.chars().filter(|c| c.is_ascii_lowercase()).collect();
         Ok(letters
             .iter()
             .map(|&letter| {
                 get_queue_len(
-                    input
+                    polymer
                         .chars()
                         .filter(|&l| l != letter && l != letter.to_ascii_uppercase()),
                 )
@@ -18,15 +34,15 @@ pub(super) const DAY5: Solution = Solution {
             .min()
             .ok_or("Empty input")?
             .to_string())
-    },
-};
+    }
+}
 
 fn get_queue_len(input: impl Iterator<Item = char>) -> usize {
     let mut queue: Vec<char> = Vec::new();
     for b in input {
-        let reacts = queue.last().map_or(false, |&a| {
-            a != b && a.to_ascii_uppercase() == b.to_ascii_uppercase()
-        });
+        let reacts = queue
+            .last()
+            .is_some_and(|&a| a != b && a.eq_ignore_ascii_case(&b));
         if reacts {
             queue.pop();
         } else {
@@ -40,7 +56,7 @@ fn get_queue_len(input: impl Iterator<Item = char>) -> usize {
 mod test {
     use crate::test;
     test!(
-        DAY5.part1,
+        Day5.part1,
         empty: "" => 0,
         example1: "aA" => 0,
         example2: "abBA" => 0,
@@ -50,7 +66,7 @@ mod test {
         input: 9202,
     );
     test!(
-        DAY5.part2,
+        Day5.part2,
         example: "dabAcCaCBAcCcaDA" => 4,
         input: 6394,
     );
@@ -1,13 +1,35 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::{Parameter, Parameters};
+use crate::puzzle::Puzzle;
 use itertools::Itertools;
 use nom::types::CompleteStr;
 use nom::{do_parse, map_res, named, tag, take_while1};
 use std::cmp::Ordering;
 use std::error::Error;
 
-pub(super) const DAY6: Solution = Solution {
-    part1: |input| {
-        let mut points = get_points(input)?;
+pub(super) struct Day6;
+
+impl Puzzle for Day6 {
+    type Input<'a> = Vec<Point>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 6;
+    const TITLE: &'static str = "Chronal Coordinates";
+    const PARAMETERS: &'static [Parameter] = &[Parameter {
+        name: "max_distance",
+        description: "total distance to all points below which a location is in the region",
+        default: 10_000,
+    }];
+
+    fn parse(input: &str) -> Result<Vec<Point>, Box<dyn Error>> {
+        Ok(input
+            .lines()
+            .map(|line| error::parse(input, line, point))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(points: &Vec<Point>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        let mut points = points.clone();
         let (min_x, max_x) = points
             .iter()
             .map(|p| p.x)
@@ -17,7 +39,7 @@ pub(super) const DAY6: Solution = Solution {
         let (min_y, max_y) = points.iter().map(|p| p.y).minmax().into_option().unwrap();
         for x in min_x..=max_x {
             for y in min_y..=max_y {
-                let mut min_distance = i32::max_value();
+                let mut min_distance = i32::MAX;
                 let mut min = None;
                 for point in &mut points {
                     let distance = (point.x - x).abs() + (point.y - y).abs();
@@ -45,22 +67,11 @@ pub(super) const DAY6: Solution = Solution {
             .ok_or("No non-infinite points")?
             .to_string();
         Ok(max_point)
-    },
-    part2: |input| Ok(find_region_size(input, 10_000)?.to_string()),
-};
+    }
 
-fn get_points(input: &str) -> Result<Vec<Point>, Box<dyn Error + '_>> {
-    input
-        .lines()
-        .map(|line| {
-            let (rest, point) = point(CompleteStr(line))?;
-            if rest.is_empty() {
-                Ok(point)
-            } else {
-                Err("Text found in a line after point".into())
-            }
-        })
-        .collect()
+    fn part2(points: &Vec<Point>, parameters: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(find_region_size(points, parameters.get("max_distance")?)?.to_string())
+    }
 }
 
 named!(
@@ -74,14 +85,14 @@ named!(
     map_res!(take_while1!(|c| char::is_digit(c, 10)), |x: CompleteStr<'_>| x.parse())
 );
 
-struct Point {
+#[derive(Clone)]
+pub(super) struct Point {
     x: i32,
     y: i32,
     count: u32,
 }
 
-fn find_region_size(input: &str, max_total_distance: i32) -> Result<usize, Box<dyn Error + '_>> {
-    let points = get_points(input)?;
+fn find_region_size(points: &[Point], max_total_distance: i32) -> Result<usize, Box<dyn Error>> {
     let range_modifier = max_total_distance / points.len() as i32;
     let (min_x, max_x) = points
         .iter()
@@ -107,15 +118,16 @@ fn find_region_size(input: &str, max_total_distance: i32) -> Result<usize, Box<d
 mod test {
     use crate::test;
     test!(
-        DAY6.part1,
+        Day6.part1,
         example: lines!("1, 1" "1, 6" "8, 3" "3, 4" "5, 5" "8, 9") => 17,
         input: 3969,
     );
     test!(
-        DAY6.part2,
+        Day6.part2,
         fn test_find_region_size() {
-            use crate::day6::find_region_size;
-            assert_eq!(find_region_size(lines!("1, 1" "1, 6" "8, 3" "3, 4" "5, 5" "8, 9"), 32).unwrap(), 16);
+            use crate::day6::{find_region_size, Day6};
+            let points = Day6::parse(lines!("1, 1" "1, 6" "8, 3" "3, 4" "5, 5" "8, 9")).unwrap();
+            assert_eq!(find_region_size(&points, 32).unwrap(), 16);
         }
         input: 42123,
     );
@@ -1,4 +1,6 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::{Parameter, Parameters};
+use crate::puzzle::Puzzle;
 use nom::types::CompleteStr;
 use nom::{anychar, do_parse, named, tag};
 use std::cmp::Reverse;
@@ -6,9 +8,46 @@ use std::collections::{BinaryHeap, HashMap};
 use std::error::Error;
 use std::mem;
 
-pub(super) const DAY7: Solution = Solution {
-    part1: |input| {
-        let mut relations = get_relation_map(input)?;
+pub(super) struct Day7;
+
+impl Puzzle for Day7 {
+    type Input<'a> = HashMap<char, StepRelations>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 7;
+    const TITLE: &'static str = "The Sum of Its Parts";
+    const PARAMETERS: &'static [Parameter] = &[
+        Parameter {
+            name: "workers",
+            description: "number of elves working on steps at once",
+            default: 5,
+        },
+        Parameter {
+            name: "delay",
+            description: "seconds added to the duration of every step",
+            default: 60,
+        },
+    ];
+
+    fn parse(input: &str) -> Result<HashMap<char, StepRelations>, Box<dyn Error>> {
+        let mut relations = HashMap::new();
+        for line in input.lines() {
+            let Dependency { requirement, then } = error::parse(input, line, dependency)?;
+            relations
+                .entry(requirement)
+                .or_insert_with(StepRelations::default)
+                .children
+                .push(then);
+            relations.entry(then).or_default().parent_count += 1;
+        }
+        Ok(relations)
+    }
+
+    fn part1(
+        relations: &HashMap<char, StepRelations>,
+        _: &Parameters,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut relations = relations.clone();
         let mut ordering = String::new();
         let mut heap = get_initial_heap(&relations);
         while let Some(Reverse(current)) = heap.pop() {
@@ -16,35 +55,19 @@ pub(super) const DAY7: Solution = Solution {
             add_children(current, &mut relations, &mut heap);
         }
         Ok(ordering)
-    },
-    part2: |input| Ok(order_in_parallel(input, 5, 60)?.to_string()),
-};
-
-fn get_relation_map(input: &str) -> Result<HashMap<char, StepRelations>, Box<dyn Error + '_>> {
-    let mut relations = HashMap::new();
-    for dependency in get_dependencies(input) {
-        let Dependency { requirement, then } = dependency?;
-        relations
-            .entry(requirement)
-            .or_insert_with(StepRelations::default)
-            .children
-            .push(then);
-        relations.entry(then).or_default().parent_count += 1;
     }
-    Ok(relations)
-}
 
-fn get_dependencies(
-    input: &str,
-) -> impl Iterator<Item = Result<Dependency, Box<dyn Error + '_>>> + '_ {
-    input.lines().map(|line| {
-        let (rest, point) = dependency(CompleteStr(line))?;
-        if rest.is_empty() {
-            Ok(point)
-        } else {
-            Err("Text found in a line after a dependency".into())
+    fn part2(
+        relations: &HashMap<char, StepRelations>,
+        parameters: &Parameters,
+    ) -> Result<String, Box<dyn Error>> {
+        let workers = parameters.get("workers")?;
+        if workers == 0 {
+            return Err("Parameter workers has invalid value 0: steps need workers".into());
         }
-    })
+        let delay = parameters.get("delay")?;
+        Ok(order_in_parallel(relations, workers, delay).to_string())
+    }
 }
 
 named!(
@@ -64,8 +87,8 @@ struct Dependency {
     then: char,
 }
 
-#[derive(Default)]
-struct StepRelations {
+#[derive(Clone, Default)]
+pub(super) struct StepRelations {
     parent_count: usize,
     children: Vec<char>,
 }
@@ -83,10 +106,7 @@ fn add_children(
     relations: &mut HashMap<char, StepRelations>,
     heap: &mut BinaryHeap<Reverse<char>>,
 ) {
-    let children = mem::replace(
-        &mut relations.get_mut(&current).unwrap().children,
-        Vec::new(),
-    );
+    let children = mem::take(&mut relations.get_mut(&current).unwrap().children);
     for child in children {
         let parent_count = &mut relations.get_mut(&child).unwrap().parent_count;
         *parent_count -= 1;
@@ -97,11 +117,11 @@ fn add_children(
 }
 
 fn order_in_parallel(
-    input: &str,
+    relations: &HashMap<char, StepRelations>,
     elves: usize,
     additional_sleep: u32,
-) -> Result<u32, Box<dyn Error + '_>> {
-    let mut relations = get_relation_map(input)?;
+) -> u32 {
+    let mut relations = relations.clone();
     let mut heap = get_initial_heap(&relations);
     let mut sleep_times = BinaryHeap::new();
     let mut time = 0;
@@ -114,7 +134,7 @@ fn order_in_parallel(
             )));
         }
         match sleep_times.pop() {
-            None => return Ok(time),
+            None => return time,
             Some(Reverse((new_time, letter))) => {
                 add_children(letter, &mut relations, &mut heap);
                 time = new_time;
@@ -127,7 +147,7 @@ fn order_in_parallel(
 mod test {
     use crate::test;
     test!(
-        DAY7.part1,
+        Day7.part1,
         example: lines!(
             "Step C must be finished before step A can begin."
             "Step C must be finished before step F can begin."
@@ -140,7 +160,7 @@ mod test {
         input: "SCLPAMQVUWNHODRTGYKBJEFXZI",
     );
     test!(
-        DAY7.part2,
+        Day7.part2,
         fn simple_example() {
             use crate::day7::order_in_parallel;
             let lines = lines!(
@@ -152,7 +172,24 @@ mod test {
                 "Step D must be finished before step E can begin."
                 "Step F must be finished before step E can begin."
             );
-            assert_eq!(order_in_parallel(lines, 2, 0).unwrap(), 15);
+            assert_eq!(order_in_parallel(&Day7::parse(lines).unwrap(), 2, 0), 15);
+        }
+        fn overridden_parameters() {
+            use crate::parameters::Parameters;
+            let lines = lines!(
+                "Step C must be finished before step A can begin."
+                "Step C must be finished before step F can begin."
+                "Step A must be finished before step B can begin."
+                "Step A must be finished before step D can begin."
+                "Step B must be finished before step E can begin."
+                "Step D must be finished before step E can begin."
+                "Step F must be finished before step E can begin."
+            );
+            let overrides = [("workers".into(), 2), ("delay".into(), 0)];
+            let parameters = Parameters::new(Day7::PARAMETERS, &overrides).unwrap();
+            assert_eq!(Day7::solve(Part::Two, lines, &parameters).unwrap(), "15");
+            let parameters = Parameters::new(Day7::PARAMETERS, &[("workers".into(), 0)]).unwrap();
+            assert!(Day7::solve(Part::Two, lines, &parameters).is_err());
         }
         input: 1234,
     );
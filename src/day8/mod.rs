@@ -1,42 +1,55 @@
-use crate::Solution;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use std::error::Error;
 
-pub(super) const DAY8: Solution = Solution {
-    part1: |input| Ok(get_node_sum(&mut get_input_iterator(input))?.to_string()),
-    part2: |input| Ok(get_root_node_value(&mut get_input_iterator(input))?.to_string()),
-};
+pub(super) struct Day8;
 
-fn get_input_iterator(input: &str) -> impl Iterator<Item = Result<usize, Box<dyn Error>>> + '_ {
-    input.split_whitespace().map(|x| Ok(x.parse()?))
+impl Puzzle for Day8 {
+    type Input<'a> = Vec<usize>;
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 8;
+    const TITLE: &'static str = "Memory Maneuver";
+
+    fn parse(input: &str) -> Result<Vec<usize>, Box<dyn Error>> {
+        Ok(input
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(numbers: &Vec<usize>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(get_node_sum(&mut numbers.iter().copied())?.to_string())
+    }
+
+    fn part2(numbers: &Vec<usize>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+        Ok(get_root_node_value(&mut numbers.iter().copied())?.to_string())
+    }
 }
 
-fn get_node_sum(
-    iter: &mut impl Iterator<Item = Result<usize, Box<dyn Error>>>,
-) -> Result<usize, Box<dyn Error>> {
-    let child_nodes = iter.next().ok_or("Missing child nodes quantity")??;
-    let metadata = iter.next().ok_or("Missing metadata")??;
+fn get_node_sum(iter: &mut impl Iterator<Item = usize>) -> Result<usize, Box<dyn Error>> {
+    let child_nodes = iter.next().ok_or("Missing child nodes quantity")?;
+    let metadata = iter.next().ok_or("Missing metadata")?;
     let mut sum = 0;
     for _ in 0..child_nodes {
         sum += get_node_sum(iter)?;
     }
     for _ in 0..metadata {
-        sum += iter.next().ok_or("Missing metadata")??;
+        sum += iter.next().ok_or("Missing metadata")?;
     }
     Ok(sum)
 }
 
-fn get_root_node_value(
-    iter: &mut impl Iterator<Item = Result<usize, Box<dyn Error>>>,
-) -> Result<usize, Box<dyn Error>> {
-    let child_nodes_count = iter.next().ok_or("Missing child nodes quantity")??;
-    let metadata = iter.next().ok_or("Missing metadata")??;
+fn get_root_node_value(iter: &mut impl Iterator<Item = usize>) -> Result<usize, Box<dyn Error>> {
+    let child_nodes_count = iter.next().ok_or("Missing child nodes quantity")?;
+    let metadata = iter.next().ok_or("Missing metadata")?;
     let mut child_nodes = Vec::new();
     for _ in 0..child_nodes_count {
         child_nodes.push(get_root_node_value(iter)?);
     }
     let mut sum = 0;
     for _ in 0..metadata {
-        let value = iter.next().ok_or("Missing metadata")??;
+        let value = iter.next().ok_or("Missing metadata")?;
         sum += if child_nodes.is_empty() {
             value
         } else {
@@ -50,12 +63,12 @@ fn get_root_node_value(
 mod test {
     use crate::test;
     test!(
-        DAY8.part1,
+        Day8.part1,
         example: "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2" => 138,
         input: 40_036,
     );
     test!(
-        DAY8.part2,
+        Day8.part2,
         example: "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2" => 66,
         input: 21_677,
     );
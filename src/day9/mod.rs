@@ -1,18 +1,37 @@
-use crate::Solution;
+use crate::error;
+use crate::parameters::Parameters;
+use crate::puzzle::Puzzle;
 use nom::types::CompleteStr;
 use nom::{do_parse, map_res, named, tag, take_while1};
 use std::error::Error;
 
-pub(super) const DAY9: Solution = Solution {
-    part1: |input| {
-        let (players, last_marble) = get_puzzle_input(input)?;
+pub(super) struct Day9;
+
+impl Puzzle for Day9 {
+    type Input<'a> = (usize, u32);
+
+    const YEAR: u16 = 2018;
+    const DAY: u8 = 9;
+    const TITLE: &'static str = "Marble Mania";
+
+    fn parse(input: &str) -> Result<(usize, u32), Box<dyn Error>> {
+        Ok(error::parse(input, input, puzzle_input)?)
+    }
+
+    fn part1(
+        &(players, last_marble): &(usize, u32),
+        _: &Parameters,
+    ) -> Result<String, Box<dyn Error>> {
         get_max_score(players, last_marble)
-    },
-    part2: |input| {
-        let (players, last_marble) = get_puzzle_input(input)?;
+    }
+
+    fn part2(
+        &(players, last_marble): &(usize, u32),
+        _: &Parameters,
+    ) -> Result<String, Box<dyn Error>> {
         get_max_score(players, last_marble * 100)
-    },
-};
+    }
+}
 
 fn get_max_score(players: usize, last_marble: u32) -> Result<String, Box<dyn Error>> {
     let mut marbles = ArrayCyclicList::new(0);
@@ -35,15 +54,6 @@ fn get_max_score(players: usize, last_marble: u32) -> Result<String, Box<dyn Err
     Ok(scores.iter().max().unwrap().to_string())
 }
 
-fn get_puzzle_input(input: &str) -> Result<(usize, u32), Box<dyn Error + '_>> {
-    let (rest, result) = puzzle_input(CompleteStr(input))?;
-    if rest.is_empty() {
-        Ok(result)
-    } else {
-        Err("Found text after input".into())
-    }
-}
-
 named!(
     puzzle_input(CompleteStr<'_>) -> (usize, u32),
     do_parse!(
@@ -154,7 +164,7 @@ struct ArrayCyclicListNode<T> {
 mod test {
     use crate::test;
     test!(
-        DAY9.part1,
+        Day9.part1,
         example1: "9 players; last marble is worth 25 points" => 32,
         example2: "10 players; last marble is worth 1618 points" => 8_317,
         example3: "13 players; last marble is worth 7999 points" => 146_373,
@@ -164,7 +174,7 @@ mod test {
         input: "464 players; last marble is worth 71730 points" => 380_705,
     );
     test!(
-        DAY9.part2,
+        Day9.part2,
         input: "464 players; last marble is worth 71730 points" => 3_171_801_582,
     );
 }
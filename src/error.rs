@@ -0,0 +1,213 @@
+use nom::types::CompleteStr;
+use nom::{Context, Err, ErrorKind, IResult};
+use serde::Serialize;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+
+/// Problem with a puzzle input, pointing at the place where it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Text doesn't follow the input format.
+    Syntax {
+        day: Option<u8>,
+        span: Span,
+        expected: String,
+    },
+    /// Text follows the input format, but the value doesn't make sense for the puzzle.
+    Invalid {
+        day: Option<u8>,
+        span: Span,
+        message: String,
+    },
+}
+
+impl Error {
+    pub fn day(&self) -> Option<u8> {
+        match *self {
+            Error::Syntax { day, .. } | Error::Invalid { day, .. } => day,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        match self {
+            Error::Syntax { span, .. } | Error::Invalid { span, .. } => span,
+        }
+    }
+
+    /// Attributes the error to a day, solutions don't know which day they are.
+    pub fn in_day(mut self, new_day: u8) -> Self {
+        match &mut self {
+            Error::Syntax { day, .. } | Error::Invalid { day, .. } => *day = Some(new_day),
+        }
+        self
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(day) = self.day() {
+            write!(f, "day {}, ", day)?;
+        }
+        let span = self.span();
+        write!(f, "line {}, column {}: ", span.line, span.column)?;
+        match self {
+            Error::Syntax { expected, .. } => match span.found() {
+                Some(found) => write!(f, "expected {}, found {:?}", expected, found),
+                None => write!(f, "expected {}, found end of line", expected),
+            },
+            Error::Invalid { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Location in an input. Lines and columns are counted from 1, columns in characters.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    /// Whole line containing the location, for displaying it.
+    pub text: String,
+}
+
+impl Span {
+    /// Locates `position`, which must be a slice of `input`.
+    pub fn new(input: &str, position: &str) -> Self {
+        let offset = (position.as_ptr() as usize)
+            .wrapping_sub(input.as_ptr() as usize)
+            .min(input.len());
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        Span {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            text: input[line_start..line_end].to_string(),
+        }
+    }
+
+    fn found(&self) -> Option<char> {
+        self.text.chars().nth(self.column - 1)
+    }
+
+    /// Writes the line with a caret under the column.
+    pub fn write_snippet(&self, mut out: impl Write) -> io::Result<()> {
+        let width = self.line.to_string().len();
+        writeln!(out, "{:w$} |", "", w = width)?;
+        writeln!(out, "{} | {}", self.line, self.text)?;
+        writeln!(
+            out,
+            "{:w$} | {:c$}^",
+            "",
+            "",
+            w = width,
+            c = self.column - 1
+        )
+    }
+}
+
+/// Runs `parser` on `text`, which must be a slice of `input`, requiring it to consume the
+/// whole text. Locations in errors are relative to `input`.
+pub fn parse<'a, T>(
+    input: &'a str,
+    text: &'a str,
+    parser: impl FnOnce(CompleteStr<'a>) -> IResult<CompleteStr<'a>, T>,
+) -> Result<T, Error> {
+    let (position, expected) = match parser(CompleteStr(text)) {
+        Ok((CompleteStr(""), output)) => return Ok(output),
+        Ok((rest, _)) if text.contains('\n') => (rest.0, "end of input"),
+        Ok((rest, _)) => (rest.0, "end of line"),
+        Err(Err::Error(Context::Code(rest, kind)))
+        | Err(Err::Failure(Context::Code(rest, kind))) => (rest.0, describe(&kind)),
+        Err(Err::Incomplete(_)) => (&text[text.len()..], "more input"),
+    };
+    Err(Error::Syntax {
+        day: None,
+        span: Span::new(input, position),
+        expected: expected.to_string(),
+    })
+}
+
+fn describe(kind: &ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::Tag | ErrorKind::Char => "literal text",
+        ErrorKind::Digit | ErrorKind::TakeWhile1 | ErrorKind::MapRes => "number",
+        ErrorKind::Alpha => "word",
+        ErrorKind::Alt => "one of the alternatives",
+        ErrorKind::Eof => "a character",
+        ErrorKind::SeparatedNonEmptyList | ErrorKind::Many1 => "a list",
+        _ => "valid input",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, Error, Span};
+    use crate::lines;
+    use nom::types::CompleteStr;
+    use nom::{do_parse, map_res, named, tag, take_while1};
+
+    #[rustfmt::skip]
+    named!(
+        pair(CompleteStr<'_>) -> (u8, u8),
+        do_parse!(a: number >> tag!(", ") >> b: number >> ((a, b)))
+    );
+
+    #[rustfmt::skip]
+    named!(
+        number(CompleteStr<'_>) -> u8,
+        map_res!(take_while1!(|c| char::is_digit(c, 10)), |x: CompleteStr<'_>| x.parse())
+    );
+
+    #[test]
+    fn locates_errors_in_lines() {
+        let input = "1, 2\n3; 4\n";
+        let line = input.lines().nth(1).unwrap();
+        let error = parse(input, line, pair).unwrap_err();
+        assert_eq!(
+            error,
+            Error::Syntax {
+                day: None,
+                span: Span {
+                    line: 2,
+                    column: 2,
+                    text: "3; 4".into()
+                },
+                expected: "literal text".into(),
+            }
+        );
+        assert_eq!(
+            error.in_day(6).to_string(),
+            "day 6, line 2, column 2: expected literal text, found ';'"
+        );
+        let error = parse("1, 2x", "1, 2x", pair).unwrap_err();
+        assert_eq!(error.span().column, 5);
+        assert!(error
+            .to_string()
+            .ends_with("expected end of line, found 'x'"));
+    }
+
+    #[test]
+    fn writes_snippets() {
+        let input = "1, 2\n3, x\n";
+        let mut out = Vec::new();
+        let error = parse(input, &input[5..9], pair).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 4: expected number, found 'x'"
+        );
+        error.span().write_snippet(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            lines!(
+                "  |"
+                "2 | 3, x"
+                "  |    ^"
+            )
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::runner::Failure;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Inputs bundled with the source, in `dayN/input` files.
+pub const BUNDLED: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+/// Environment variable overriding the inputs directory.
+pub const ENVIRONMENT_VARIABLE: &str = "AOC_INPUTS";
+
+/// Directory used when it exists and no other one was chosen.
+pub const DEFAULT_DIRECTORY: &str = "inputs";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputFile {
+    /// Name of the input, `None` for the unnamed `dayNN.txt` one.
+    pub name: Option<String>,
+    pub path: PathBuf,
+}
+
+impl InputFile {
+    pub fn read(&self) -> Result<String, Failure> {
+        fs::read_to_string(&self.path).map_err(|e| Failure {
+            message: format!("Cannot read {}", self.path.display()),
+            sources: vec![e.to_string()],
+            span: None,
+        })
+    }
+}
+
+/// Chooses the inputs directory, trying the given one, then the environment variable, then
+/// `inputs` in the current directory and finally the bundled inputs.
+pub fn directory(chosen: Option<PathBuf>) -> PathBuf {
+    chosen
+        .or_else(|| env::var_os(ENVIRONMENT_VARIABLE).map(PathBuf::from))
+        .or_else(|| Some(PathBuf::from(DEFAULT_DIRECTORY)).filter(|d| d.is_dir()))
+        .unwrap_or_else(|| PathBuf::from(BUNDLED))
+}
+
+/// Finds inputs for a day, named like `day07.txt` or `day07.alice.txt`. The bundled
+/// `day7/input` layout is understood too. The unnamed input comes first, then the named ones
+/// sorted by name.
+pub fn find(directory: &Path, day: u8) -> Result<Vec<InputFile>, Failure> {
+    let entries = fs::read_dir(directory).map_err(|e| Failure {
+        message: format!("Cannot read {}", directory.display()),
+        sources: vec![e.to_string()],
+        span: None,
+    })?;
+    let prefix = format!("day{:02}", day);
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?.strip_prefix(&prefix)?;
+            let name = match name.strip_suffix(".txt")? {
+                "" => None,
+                name => Some(
+                    name.strip_prefix('.')
+                        .filter(|n| !n.is_empty())?
+                        .to_string(),
+                ),
+            };
+            Some(InputFile { name, path })
+        })
+        .collect();
+    let bundled = directory.join(format!("day{}", day)).join("input");
+    if files.iter().all(|f| f.name.is_some()) && bundled.is_file() {
+        files.push(InputFile {
+            name: None,
+            path: bundled,
+        });
+    }
+    if files.is_empty() {
+        return Err(format!("No input for day {} in {}", day, directory.display()).into());
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find, BUNDLED};
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn finds_named_inputs() {
+        let directory = env::temp_dir().join(format!("aoc-inputs-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for file in &[
+            "day07.txt",
+            "day07.bob.txt",
+            "day07.alice.txt",
+            "day07..txt",
+            "day07.md",
+            "day17.txt",
+        ] {
+            fs::write(directory.join(file), "").unwrap();
+        }
+        let names: Vec<_> = find(&directory, 7)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+        assert_eq!(names, [None, Some("alice".into()), Some("bob".into())]);
+        assert_eq!(find(&directory, 17).unwrap().len(), 1);
+        assert_eq!(
+            find(&directory, 1).unwrap_err().message,
+            format!("No input for day 1 in {}", directory.display())
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn understands_bundled_layout() {
+        let files = find(Path::new(BUNDLED), 7).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, None);
+        assert!(files[0].read().unwrap().starts_with("Step "));
+        assert!(find(Path::new(BUNDLED), 9).is_err());
+    }
+}
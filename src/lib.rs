@@ -0,0 +1,72 @@
+use puzzle::Registry;
+use std::sync::OnceLock;
+
+pub mod bench;
+pub mod cpu;
+mod day1;
+mod day10;
+mod day11;
+mod day12;
+mod day13;
+mod day14;
+pub mod day15;
+mod day16;
+mod day17;
+mod day18;
+mod day19;
+mod day2;
+pub mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
+mod day25;
+mod day3;
+mod day4;
+mod day5;
+mod day6;
+mod day7;
+mod day8;
+mod day9;
+pub mod error;
+pub mod inputs;
+pub mod parameters;
+pub mod puzzle;
+pub mod runner;
+#[cfg(test)]
+mod testmacros;
+pub mod verify;
+
+/// Every solved puzzle.
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        Registry::new(&[
+            &day1::Day1,
+            &day2::Day2,
+            &day3::Day3,
+            &day4::Day4,
+            &day5::Day5,
+            &day6::Day6,
+            &day7::Day7,
+            &day8::Day8,
+            &day9::Day9,
+            &day10::Day10,
+            &day11::Day11,
+            &day12::Day12,
+            &day13::Day13,
+            &day14::Day14,
+            &day15::Day15,
+            &day16::Day16,
+            &day17::Day17,
+            &day18::Day18,
+            &day19::Day19,
+            &day20::Day20,
+            &day21::Day21,
+            &day22::Day22,
+            &day23::Day23,
+            &day24::Day24,
+            &day25::Day25,
+        ])
+    })
+}
@@ -1,99 +1,345 @@
-use clap::Parser;
+use advent_of_code_2018::cpu::assembler::assemble;
+use advent_of_code_2018::cpu::cfg::Cfg;
+use advent_of_code_2018::cpu::decompiler::Decompiler;
+use advent_of_code_2018::cpu::tracer::{LogFormat, Trace};
+use advent_of_code_2018::cpu::{debugger::Debugger, disassembler::Disassembler};
+use advent_of_code_2018::day15::{replay::Replay, Game};
+use advent_of_code_2018::parameters::{self, Parameters};
+use advent_of_code_2018::runner::{self, Format, Part};
+use advent_of_code_2018::{bench, inputs, registry, verify};
+use clap::{Args, Parser, Subcommand};
 use std::error::Error;
-use std::io::{self, Read, Write};
-
-mod cpu;
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day2;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-#[cfg(test)]
-mod testmacros;
-
-struct Solution {
-    part1: fn(&str) -> Result<String, Box<dyn Error + '_>>,
-    part2: fn(&str) -> Result<String, Box<dyn Error + '_>>,
-}
-
-const SOLUTIONS: &[Solution] = &[
-    day1::DAY1,
-    day2::DAY2,
-    day3::DAY3,
-    day4::DAY4,
-    day5::DAY5,
-    day6::DAY6,
-    day7::DAY7,
-    day8::DAY8,
-    day9::DAY9,
-    day10::DAY10,
-    day11::DAY11,
-    day12::DAY12,
-    day13::DAY13,
-    day14::DAY14,
-    day15::DAY15,
-    day16::DAY16,
-    day17::DAY17,
-    day18::DAY18,
-    day19::DAY19,
-    day20::DAY20,
-    day21::DAY21,
-    day22::DAY22,
-    day23::DAY23,
-    day24::DAY24,
-    day25::DAY25,
-];
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
 
 #[derive(Parser)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Options {
     /// Day for which a solution should be ran
-    day: u8,
-    /// Input, if not provided taken from stdin
+    #[clap(required_unless_present_any = &["all", "days"])]
+    day: Option<u8>,
+    /// Year of the puzzles, the most recent one by default
+    #[clap(long, value_parser = runner::parse_year, global = true)]
+    year: Option<u16>,
+    /// Input, `-` to read it from stdin, if not provided taken from the inputs directory
     input: Option<String>,
+    /// Run solutions for every day
+    #[clap(long, conflicts_with_all = &["day", "days"])]
+    all: bool,
+    /// Range of days to run, like 3..=9
+    #[clap(long, value_parser = runner::parse_days, conflicts_with = "day")]
+    days: Option<RangeInclusive<u8>>,
+    /// Directory with day07.txt or day07.NAME.txt input files, by default AOC_INPUTS, ./inputs
+    /// if it exists or the bundled inputs
+    #[clap(long, conflicts_with = "input")]
+    inputs: Option<PathBuf>,
+    /// Run only this part, 1 or 2
+    #[clap(long, value_parser = runner::parse_part)]
+    part: Option<Part>,
+    /// Number of parts ran at once with --all and --days, one per CPU by default
+    #[clap(
+        long,
+        default_value_t = 0,
+        hide_default_value = true,
+        conflicts_with = "day"
+    )]
+    jobs: usize,
+    /// Seconds after which a part ran with --all or --days is reported as timed out, 0 to wait
+    /// indefinitely
+    #[clap(long, default_value_t = 60, conflicts_with = "day")]
+    timeout: u64,
+    /// Override a parameter of the day, like workers=2
+    #[clap(long = "param", value_parser = parameters::parse_assignment, requires = "day")]
+    params: Vec<(String, i64)>,
+    /// Output format
+    #[clap(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Write a log of every elf-code instruction ran by days 19 and 21 to this directory, along
+    /// with a summary of the hottest instructions and loop back edges. Loops the optimizer runs
+    /// in closed form aren't traced
+    #[clap(long, requires = "day")]
+    trace: Option<PathBuf>,
+    /// Format of the trace log
+    #[clap(long, value_enum, default_value_t = LogFormat::Csv, requires = "trace")]
+    trace_format: LogFormat,
+    /// Draw the day 15 combat after every round instead of solving it
+    #[clap(long, requires = "day", conflicts_with = "trace")]
+    replay: bool,
+    /// Milliseconds to wait after each round of the replay, redrawing the board in place on a
+    /// terminal
+    #[clap(long, default_value_t = 0, requires = "replay")]
+    frame_delay: u64,
+    /// First round drawn by the replay
+    #[clap(long, default_value_t = 0, requires = "replay")]
+    from_round: u32,
+    /// List the moves, attacks and deaths of every unit below each round of the replay
+    #[clap(long, requires = "replay")]
+    events: bool,
+    /// Attack power of the elves in the replay
+    #[clap(long, default_value_t = 3, requires = "replay")]
+    elf_power: u8,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Measure how long each part takes to run
+    Bench {
+        /// Range of days to benchmark, like 3..=9, every day by default
+        #[clap(long, value_parser = runner::parse_days)]
+        days: Option<RangeInclusive<u8>>,
+        /// Directory with input files, chosen like for running solutions
+        #[clap(long)]
+        inputs: Option<PathBuf>,
+        /// Number of unmeasured runs before measuring
+        #[clap(long, default_value_t = 3)]
+        warmup: u32,
+        /// Number of measured runs
+        #[clap(long, default_value_t = 10)]
+        iterations: u32,
+        /// Save measurements as a baseline to this file
+        #[clap(long)]
+        save: Option<PathBuf>,
+        /// Compare measurements against a baseline saved with --save
+        #[clap(long)]
+        baseline: Option<PathBuf>,
+        /// Median slowdown in percent above which a part is reported as a regression
+        #[clap(long, default_value_t = 10.0)]
+        threshold: f64,
+    },
+    /// Check answers against a JSON or TOML manifest of known answers
+    Verify {
+        /// Manifest file, input paths in it are relative to its directory
+        manifest: PathBuf,
+    },
+    /// Print an elf-code program of day 19 or 21 as pseudo-code
+    Disassemble(Program),
+    /// Step through an elf-code program of day 19 or 21 interactively
+    Debug(Program),
+    /// Print basic blocks, dominators and loops of an elf-code program of day 19 or 21
+    Cfg {
+        #[clap(flatten)]
+        program: Program,
+        /// Print the graph in Graphviz DOT format instead
+        #[clap(long)]
+        dot: bool,
+    },
+    /// Print an elf-code program of day 19 or 21 as structured pseudo-Rust
+    Decompile(Program),
+}
+
+/// Elf-code program read by the commands analysing them.
+#[derive(Args)]
+struct Program {
+    /// Day whose input is used
+    #[clap(default_value_t = 19)]
+    day: u8,
+    /// Program file, which may use the assembly dialect with labels and aliases, by default the
+    /// first input of the day in the inputs directory
+    program: Option<PathBuf>,
+    /// Directory with input files, chosen like for running solutions
+    #[clap(long, conflicts_with = "program")]
+    inputs: Option<PathBuf>,
+}
+
+impl Program {
+    fn read(self) -> Result<String, Box<dyn Error>> {
+        match self.program {
+            Some(path) => Ok(fs::read_to_string(path)?),
+            None => Ok(inputs::find(&inputs::directory(self.inputs), self.day)?[0].read()?),
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let opt = Options::parse();
-    let solution = SOLUTIONS
-        .get(usize::from(opt.day) - 1)
-        .ok_or("Day number out of range")?;
-    let input = match opt.input {
-        Some(input) => input,
-        None => {
+    let year = opt.year.unwrap_or_else(runner::default_year);
+    match opt.command {
+        Some(Command::Bench {
+            days,
+            inputs,
+            warmup,
+            iterations,
+            save,
+            baseline,
+            threshold,
+        }) => {
+            let settings = bench::Settings { warmup, iterations };
+            let measurements = bench::measure_days(
+                year,
+                days.unwrap_or(all_days(year)),
+                &inputs::directory(inputs),
+                &settings,
+            );
+            let baseline = baseline.as_deref().map(bench::load_baseline).transpose()?;
+            let regressions =
+                bench::write_report(io::stdout(), &measurements, baseline.as_ref(), threshold)?;
+            if let Some(save) = save {
+                bench::save_baseline(&save, &measurements)?;
+            }
+            if regressions != 0 {
+                return Err(format!("Found {} regressions", regressions).into());
+            }
+            return Ok(());
+        }
+        Some(Command::Verify { manifest }) => {
+            let checks = verify::verify(
+                &verify::load_manifest(&manifest)?,
+                manifest.parent().unwrap_or_else(|| Path::new("")),
+            );
+            let failures = verify::write_report(io::stdout(), &checks)?;
+            if failures != 0 {
+                return Err(format!("{} of {} checks failed", failures, checks.len()).into());
+            }
+            return Ok(());
+        }
+        Some(Command::Disassemble(program)) => {
+            let cpu = assemble(&program.read()?, [0; 6])?;
+            Disassembler::new(&cpu).write(io::stdout())?;
+            return Ok(());
+        }
+        Some(Command::Debug(program)) => {
+            let cpu = assemble(&program.read()?, [0; 6])?;
+            Debugger::new(cpu).repl(io::stdin().lock(), io::stdout())?;
+            return Ok(());
+        }
+        Some(Command::Cfg { program, dot }) => {
+            let cpu = assemble(&program.read()?, [0; 6])?;
+            let cfg = Cfg::new(&cpu);
+            if dot {
+                cfg.write_dot(&cpu, io::stdout())?;
+            } else {
+                cfg.write_summary(io::stdout())?;
+            }
+            return Ok(());
+        }
+        Some(Command::Decompile(program)) => {
+            let cpu = assemble(&program.read()?, [0; 6])?;
+            Decompiler::new(&cpu).write(io::stdout())?;
+            return Ok(());
+        }
+        None => {}
+    }
+    let parts = match opt.part {
+        Some(part) => vec![part],
+        None => Part::ALL.to_vec(),
+    };
+    let day = match (opt.day, opt.days) {
+        (Some(day), _) => day,
+        (None, days) => {
+            let schedule = runner::Schedule {
+                threads: opt.jobs,
+                timeout: Some(Duration::from_secs(opt.timeout)).filter(|t| !t.is_zero()),
+            };
+            let outcomes = runner::run_days(
+                year,
+                days.unwrap_or(all_days(year)),
+                &parts,
+                &inputs::directory(opt.inputs),
+                &schedule,
+            )?;
+            match opt.format {
+                Format::Ndjson => {
+                    for outcome in outcomes {
+                        runner::write_ndjson(io::stdout(), &[outcome])?;
+                    }
+                }
+                format => {
+                    runner::write_outcomes(io::stdout(), format, &outcomes.collect::<Vec<_>>())?
+                }
+            }
+            return Ok(());
+        }
+    };
+    Parameters::new(runner::get_solution(year, day)?.parameters(), &opt.params)?;
+    if opt.trace.is_some() && !(year == 2018 && (day == 19 || day == 21)) {
+        return Err("--trace is only supported by days 19 and 21 of 2018".into());
+    }
+    if opt.replay && !(year == 2018 && day == 15) {
+        return Err("--replay is only supported by day 15 of 2018".into());
+    }
+    let inputs = match opt.input {
+        Some(input) if input == "-" => {
             let mut input = String::new();
             io::stdin().read_to_string(&mut input)?;
-            input
+            vec![(None, input)]
         }
+        Some(input) => vec![(None, input)],
+        None => inputs::find(&inputs::directory(opt.inputs), day)?
+            .into_iter()
+            .map(|file| Ok((file.name.clone(), file.read()?)))
+            .collect::<Result<_, runner::Failure>>()?,
     };
-    writeln!(
-        io::stdout(),
-        "Part 1: {}",
-        (solution.part1)(&input).map_err(|e| e.to_string())?
-    )?;
-    writeln!(
-        io::stdout(),
-        "Part 2: {}",
-        (solution.part2)(&input).map_err(|e| e.to_string())?
-    )?;
+    if opt.replay {
+        let mut replay = Replay::new()
+            .from_round(opt.from_round)
+            .with_delay(Duration::from_millis(opt.frame_delay));
+        if opt.events {
+            replay = replay.with_events();
+        }
+        if opt.frame_delay != 0 && io::stdout().is_terminal() {
+            replay = replay.clearing();
+        }
+        for (_, input) in &inputs {
+            replay.play(Game::new(input, opt.elf_power), io::stdout().lock())?;
+        }
+        return Ok(());
+    }
+    let format = opt.trace_format;
+    let mut outcomes = Vec::new();
+    for (name, input) in &inputs {
+        let trace = opt.trace.as_ref().map(|directory| Trace {
+            directory: directory.clone(),
+            format,
+            input: name.clone(),
+        });
+        runner::run_parts(
+            year,
+            day,
+            &parts,
+            input,
+            &opt.params,
+            trace.as_ref(),
+            &mut |outcome| {
+                outcomes.push(runner::Outcome {
+                    input: name.clone(),
+                    ..outcome
+                })
+            },
+        );
+    }
+    if opt.format != Format::Text {
+        runner::write_outcomes(io::stdout(), opt.format, &outcomes)?;
+        return Ok(());
+    }
+    let mut failed = false;
+    for outcome in outcomes {
+        let part = match &outcome.input {
+            Some(name) => format!("Part {} ({})", outcome.part, name),
+            None => format!("Part {}", outcome.part),
+        };
+        match outcome.result {
+            Ok(answer) => writeln!(io::stdout(), "{}: {}", part, answer)?,
+            Err(failure) => {
+                write!(io::stderr(), "{}: ", part)?;
+                failure.write_report(io::stderr())?;
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
     Ok(())
 }
+
+fn all_days(year: u16) -> RangeInclusive<u8> {
+    let mut days = registry().days(year);
+    let first = days.next().unwrap_or(1);
+    first..=days.last().unwrap_or(first)
+}
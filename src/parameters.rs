@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+
+/// Constant used by a solution that can be overridden from the command line.
+#[derive(Copy, Clone, Debug)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: i64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Parameters {
+    values: HashMap<&'static str, i64>,
+}
+
+impl Parameters {
+    pub fn defaults(declared: &[Parameter]) -> Self {
+        Parameters {
+            values: declared.iter().map(|p| (p.name, p.default)).collect(),
+        }
+    }
+
+    pub fn new(declared: &[Parameter], overrides: &[(String, i64)]) -> Result<Self, String> {
+        let mut parameters = Self::defaults(declared);
+        for (name, value) in overrides {
+            let parameter = declared
+                .iter()
+                .find(|p| p.name == name)
+                .ok_or_else(|| unknown_parameter(name, declared))?;
+            parameters.values.insert(parameter.name, *value);
+        }
+        Ok(parameters)
+    }
+
+    pub fn get<T>(&self, name: &str) -> Result<T, Box<dyn Error>>
+    where
+        T: TryFrom<i64>,
+        T::Error: Error + 'static,
+    {
+        let value = *self
+            .values
+            .get(name)
+            .ok_or_else(|| format!("Parameter {} is not declared", name))?;
+        T::try_from(value)
+            .map_err(|e| format!("Parameter {} has invalid value {}: {}", name, value, e).into())
+    }
+}
+
+fn unknown_parameter(name: &str, declared: &[Parameter]) -> String {
+    if declared.is_empty() {
+        return format!("Unknown parameter {}, this day has no parameters", name);
+    }
+    let expected: Vec<_> = declared
+        .iter()
+        .map(|p| format!("{} ({}, default {})", p.name, p.description, p.default))
+        .collect();
+    format!(
+        "Unknown parameter {}, expected one of: {}",
+        name,
+        expected.join(", ")
+    )
+}
+
+pub fn parse_assignment(text: &str) -> Result<(String, i64), String> {
+    let (name, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("Expected name=value, got {:?}", text))?;
+    let value = value
+        .parse()
+        .map_err(|e| format!("Invalid value for {}: {}", name, e))?;
+    Ok((name.to_string(), value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_assignment, Parameter, Parameters};
+
+    const DECLARED: &[Parameter] = &[
+        Parameter {
+            name: "workers",
+            description: "number of workers",
+            default: 5,
+        },
+        Parameter {
+            name: "delay",
+            description: "additional delay",
+            default: 60,
+        },
+    ];
+
+    #[test]
+    fn overrides_defaults() {
+        let parameters = Parameters::new(DECLARED, &[("delay".into(), 0)]).unwrap();
+        assert_eq!(parameters.get::<usize>("workers").unwrap(), 5);
+        assert_eq!(parameters.get::<u32>("delay").unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_unknown_parameters() {
+        let error = Parameters::new(DECLARED, &[("elves".into(), 2)]).unwrap_err();
+        assert!(error.starts_with("Unknown parameter elves"));
+        assert!(error.contains("workers (number of workers, default 5)"));
+        assert!(Parameters::new(&[], &[("elves".into(), 2)]).is_err());
+    }
+
+    #[test]
+    fn checks_types() {
+        let parameters = Parameters::new(DECLARED, &[("workers".into(), -1)]).unwrap();
+        assert!(parameters.get::<usize>("workers").is_err());
+        assert_eq!(parameters.get::<i8>("workers").unwrap(), -1);
+        assert!(parameters.get::<i8>("elves").is_err());
+    }
+
+    #[test]
+    fn parses_assignments() {
+        assert_eq!(
+            parse_assignment("workers=2").unwrap(),
+            ("workers".into(), 2)
+        );
+        assert!(parse_assignment("workers").is_err());
+        assert!(parse_assignment("workers=two").is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::cpu::tracer::Trace;
+use crate::parameters::{Parameter, Parameters};
+use crate::runner::Part;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Solution of a single puzzle. Input is parsed once and shared by both parts.
+pub trait Puzzle {
+    /// Parsed input, which may borrow from the text it was parsed from.
+    type Input<'a>;
+
+    const YEAR: u16;
+    const DAY: u8;
+    const TITLE: &'static str;
+    /// Constants which can be overridden from the command line.
+    const PARAMETERS: &'static [Parameter] = &[];
+
+    fn parse(input: &str) -> Result<Self::Input<'_>, Box<dyn Error>>;
+
+    fn part1(input: &Self::Input<'_>, parameters: &Parameters) -> Result<String, Box<dyn Error>>;
+
+    fn part2(input: &Self::Input<'_>, parameters: &Parameters) -> Result<String, Box<dyn Error>>;
+
+    /// Runs a part, along with details of how its answer was found for days which have any.
+    fn answer(
+        part: Part,
+        input: &Self::Input<'_>,
+        parameters: &Parameters,
+    ) -> Result<Answer, Box<dyn Error>> {
+        let answer = match part {
+            Part::One => Self::part1(input, parameters)?,
+            Part::Two => Self::part2(input, parameters)?,
+        };
+        Ok(answer.into())
+    }
+
+    /// Runs a part while tracing the elf-code it executes, for days which run any.
+    fn trace(
+        part: Part,
+        _input: &Self::Input<'_>,
+        _parameters: &Parameters,
+        _trace: &Trace,
+    ) -> Result<String, Box<dyn Error>> {
+        Err(format!(
+            "Part {} of day {} doesn't run elf-code to trace",
+            part,
+            Self::DAY
+        )
+        .into())
+    }
+
+    /// Parses the input and runs a part on it.
+    fn solve(part: Part, input: &str, parameters: &Parameters) -> Result<String, Box<dyn Error>> {
+        let input = Self::parse(input)?;
+        match part {
+            Part::One => Self::part1(&input, parameters),
+            Part::Two => Self::part2(&input, parameters),
+        }
+    }
+}
+
+/// Answer of a part, with details shown next to it by structured output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Answer {
+    pub answer: String,
+    pub details: Option<Value>,
+}
+
+impl Answer {
+    pub fn with_details(answer: String, details: &impl Serialize) -> serde_json::Result<Self> {
+        Ok(Answer {
+            answer,
+            details: Some(serde_json::to_value(details)?),
+        })
+    }
+}
+
+impl From<String> for Answer {
+    fn from(answer: String) -> Self {
+        Answer {
+            answer,
+            details: None,
+        }
+    }
+}
+
+/// Object safe counterpart of [`Puzzle`], implemented for every puzzle.
+pub trait Solution: Sync {
+    fn year(&self) -> u16;
+    fn day(&self) -> u8;
+    fn title(&self) -> &'static str;
+    fn parameters(&self) -> &'static [Parameter];
+    fn parse<'a>(&self, input: &'a str) -> Result<Box<dyn Parsed + 'a>, Box<dyn Error>>;
+}
+
+/// Input parsed by [`Solution::parse`], ready to run parts on.
+pub trait Parsed {
+    fn run(&self, part: Part, parameters: &Parameters) -> Result<String, Box<dyn Error>>;
+
+    fn answer(&self, part: Part, parameters: &Parameters) -> Result<Answer, Box<dyn Error>>;
+
+    fn trace(
+        &self,
+        part: Part,
+        parameters: &Parameters,
+        trace: &Trace,
+    ) -> Result<String, Box<dyn Error>>;
+}
+
+impl<P: Puzzle + Sync + 'static> Solution for P {
+    fn year(&self) -> u16 {
+        P::YEAR
+    }
+
+    fn day(&self) -> u8 {
+        P::DAY
+    }
+
+    fn title(&self) -> &'static str {
+        P::TITLE
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        P::PARAMETERS
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Result<Box<dyn Parsed + 'a>, Box<dyn Error>> {
+        Ok(Box::new(ParsedInput::<P>(P::parse(input)?)))
+    }
+}
+
+struct ParsedInput<'a, P: Puzzle>(P::Input<'a>);
+
+impl<P: Puzzle> Parsed for ParsedInput<'_, P> {
+    fn run(&self, part: Part, parameters: &Parameters) -> Result<String, Box<dyn Error>> {
+        match part {
+            Part::One => P::part1(&self.0, parameters),
+            Part::Two => P::part2(&self.0, parameters),
+        }
+    }
+
+    fn answer(&self, part: Part, parameters: &Parameters) -> Result<Answer, Box<dyn Error>> {
+        P::answer(part, &self.0, parameters)
+    }
+
+    fn trace(
+        &self,
+        part: Part,
+        parameters: &Parameters,
+        trace: &Trace,
+    ) -> Result<String, Box<dyn Error>> {
+        P::trace(part, &self.0, parameters, trace)
+    }
+}
+
+/// Solutions keyed by year and day.
+pub struct Registry {
+    solutions: BTreeMap<(u16, u8), &'static dyn Solution>,
+}
+
+impl Registry {
+    /// Creates a registry, panicking when a day is solved twice.
+    pub fn new(solutions: &[&'static dyn Solution]) -> Self {
+        let mut registry = Registry {
+            solutions: BTreeMap::new(),
+        };
+        for &solution in solutions {
+            let key = (solution.year(), solution.day());
+            let previous = registry.solutions.insert(key, solution);
+            assert!(
+                previous.is_none(),
+                "Year {} day {} is solved twice",
+                key.0,
+                key.1
+            );
+        }
+        registry
+    }
+
+    pub fn get(&self, year: u16, day: u8) -> Option<&'static dyn Solution> {
+        self.solutions.get(&(year, day)).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'static dyn Solution> + '_ {
+        self.solutions.values().copied()
+    }
+
+    /// Solved days of a year, in order.
+    pub fn days(&self, year: u16) -> impl Iterator<Item = u8> + '_ {
+        self.solutions
+            .range((year, 0)..=(year, u8::MAX))
+            .map(|(&(_, day), _)| day)
+    }
+
+    pub fn latest_year(&self) -> Option<u16> {
+        self.solutions.keys().next_back().map(|&(year, _)| year)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Puzzle, Registry};
+    use crate::parameters::Parameters;
+    use crate::runner::Part;
+    use std::error::Error;
+
+    struct Sum<const YEAR: u16, const DAY: u8>;
+
+    impl<const YEAR: u16, const DAY: u8> Puzzle for Sum<YEAR, DAY> {
+        type Input<'a> = Vec<&'a str>;
+
+        const YEAR: u16 = YEAR;
+        const DAY: u8 = DAY;
+        const TITLE: &'static str = "Sum";
+
+        fn parse(input: &str) -> Result<Vec<&str>, Box<dyn Error>> {
+            Ok(input.split(',').collect())
+        }
+
+        fn part1(input: &Vec<&str>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+            Ok(input.len().to_string())
+        }
+
+        fn part2(input: &Vec<&str>, _: &Parameters) -> Result<String, Box<dyn Error>> {
+            Ok(input.concat())
+        }
+    }
+
+    #[test]
+    fn keeps_years_side_by_side() {
+        let registry = Registry::new(&[&Sum::<2018, 3>, &Sum::<2017, 25>, &Sum::<2018, 1>]);
+        assert_eq!(registry.latest_year(), Some(2018));
+        assert_eq!(registry.days(2018).collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(registry.days(2017).collect::<Vec<_>>(), [25]);
+        assert!(registry.get(2017, 1).is_none());
+        let solution = registry.get(2017, 25).unwrap();
+        let parsed = solution.parse("a,b,c").unwrap();
+        let parameters = Parameters::defaults(solution.parameters());
+        assert_eq!(parsed.run(Part::One, &parameters).unwrap(), "3");
+        assert_eq!(parsed.run(Part::Two, &parameters).unwrap(), "abc");
+    }
+
+    #[test]
+    #[should_panic(expected = "Year 2018 day 3 is solved twice")]
+    fn rejects_duplicate_days() {
+        Registry::new(&[&Sum::<2018, 3>, &Sum::<2018, 3>]);
+    }
+}
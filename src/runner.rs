@@ -0,0 +1,902 @@
+use crate::cpu::tracer::Trace;
+use crate::error::{self, Span};
+use crate::inputs;
+use crate::parameters::Parameters;
+use crate::puzzle::{Answer, Solution};
+use crate::registry;
+use clap::ValueEnum;
+use itertools::Itertools;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "u8")]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const ALL: [Part; 2] = [Part::One, Part::Two];
+}
+
+impl TryFrom<u8> for Part {
+    type Error = String;
+
+    fn try_from(part: u8) -> Result<Self, String> {
+        match part {
+            1 => Ok(Part::One),
+            2 => Ok(Part::Two),
+            _ => Err(format!("Part {} doesn't exist, expected 1 or 2", part)),
+        }
+    }
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::One => f.pad("1"),
+            Part::Two => f.pad("2"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+    Ndjson,
+}
+
+pub struct Outcome {
+    pub year: u16,
+    pub day: u8,
+    pub part: Part,
+    /// Name of the input file, if it has one.
+    pub input: Option<String>,
+    pub result: Result<String, Failure>,
+    /// Details of how the answer was found, for days which give any.
+    pub details: Option<Value>,
+    /// Time spent parsing the input, shared by parts ran on the same input.
+    pub parse_elapsed: Duration,
+    /// Time spent running the part, without parsing.
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub message: String,
+    pub sources: Vec<String>,
+    /// Location in the input, if the error points at one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+impl Failure {
+    /// Converts an error returned by a solution, attributing input errors to its day.
+    fn from_solution_error(error: Box<dyn Error>, day: u8) -> Self {
+        match error.downcast::<error::Error>() {
+            Ok(e) => Failure::from_error(&e.in_day(day)),
+            Err(e) => Failure::from_error(&*e),
+        }
+    }
+
+    pub fn from_error(error: &(dyn Error + 'static)) -> Self {
+        let mut sources = Vec::new();
+        let mut source = error.source();
+        while let Some(error) = source {
+            sources.push(error.to_string());
+            source = error.source();
+        }
+        Failure {
+            message: error.to_string(),
+            sources,
+            span: error
+                .downcast_ref::<error::Error>()
+                .map(|e| e.span().clone()),
+        }
+    }
+
+    /// Writes the message followed by the offending input line, if there is one.
+    pub fn write_report(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "error: {}", self)?;
+        if let Some(span) = &self.span {
+            span.write_snippet(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure {
+            message,
+            sources: Vec::new(),
+            span: None,
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for source in &self.sources {
+            write!(f, ": {}", source)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Failure {}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    year: u16,
+    day: u8,
+    part: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input: Option<&'a str>,
+    answer: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<&'a Value>,
+    parse_ns: u64,
+    duration_ns: u64,
+    error: Option<&'a Failure>,
+}
+
+impl Outcome {
+    fn failed(year: u16, day: u8, part: Part, input: Option<String>, failure: Failure) -> Self {
+        Outcome {
+            year,
+            day,
+            part,
+            input,
+            result: Err(failure),
+            details: None,
+            parse_elapsed: Duration::default(),
+            elapsed: Duration::default(),
+        }
+    }
+
+    fn record(&self) -> Record<'_> {
+        Record {
+            year: self.year,
+            day: self.day,
+            part: match self.part {
+                Part::One => 1,
+                Part::Two => 2,
+            },
+            input: self.input.as_deref(),
+            answer: self.result.as_ref().ok().map(String::as_str),
+            details: self.details.as_ref(),
+            parse_ns: self.parse_elapsed.as_nanos() as u64,
+            duration_ns: self.elapsed.as_nanos() as u64,
+            error: self.result.as_ref().err(),
+        }
+    }
+}
+
+pub fn get_solution(year: u16, day: u8) -> Result<&'static dyn Solution, String> {
+    registry()
+        .get(year, day)
+        .ok_or_else(|| format!("No solution for day {} of {}", day, year))
+}
+
+/// Most recent year with solutions, used when no year is chosen.
+pub fn default_year() -> u16 {
+    registry()
+        .latest_year()
+        .expect("No solutions are registered")
+}
+
+pub fn parse_year(text: &str) -> Result<u16, String> {
+    let year = text
+        .parse()
+        .map_err(|e| format!("Invalid year {:?}: {}", text, e))?;
+    if registry().days(year).next().is_none() {
+        return Err(format!("No solutions for {}", year));
+    }
+    Ok(year)
+}
+
+pub fn parse_part(text: &str) -> Result<Part, String> {
+    Part::try_from(text.parse::<u8>().map_err(|e| e.to_string())?)
+}
+
+pub fn parse_days(text: &str) -> Result<RangeInclusive<u8>, String> {
+    let parse = |day: &str| {
+        day.trim()
+            .parse::<u8>()
+            .map_err(|e| format!("Invalid day {:?}: {}", day, e))
+    };
+    let range = if let Some((start, end)) = text.split_once("..=") {
+        parse(start)?..=parse(end)?
+    } else if let Some((start, end)) = text.split_once("..") {
+        let end = parse(end)?;
+        parse(start)?..=end.checked_sub(1).ok_or("Empty range of days")?
+    } else {
+        let day = parse(text)?;
+        day..=day
+    };
+    let max_day = 25;
+    if range.is_empty() {
+        Err("Empty range of days".into())
+    } else if *range.start() == 0 || *range.end() > max_day {
+        Err(format!("Days must be between 1 and {}", max_day))
+    } else {
+        Ok(range)
+    }
+}
+
+/// How jobs ran by [`run_days`] are spread across threads.
+pub struct Schedule {
+    /// Number of jobs ran at once, 0 to use one per CPU.
+    pub threads: usize,
+    /// Time after which a job is reported as timed out, it keeps running in the background.
+    pub timeout: Option<Duration>,
+}
+
+struct Job {
+    day: u8,
+    input: Option<String>,
+    text: Result<Arc<str>, Failure>,
+    /// Index of the outcome of the first part.
+    first: usize,
+}
+
+/// Runs parts of given days for every input found in a directory in parallel, returning
+/// outcomes in order as they become available.
+pub fn run_days(
+    year: u16,
+    days: RangeInclusive<u8>,
+    parts: &[Part],
+    directory: &Path,
+    schedule: &Schedule,
+) -> Result<Outcomes, ThreadPoolBuildError> {
+    let mut jobs = Vec::new();
+    for day in days {
+        let mut push = |input, text| {
+            jobs.push(Job {
+                day,
+                input,
+                text,
+                first: jobs.len() * parts.len(),
+            })
+        };
+        match inputs::find(directory, day) {
+            Ok(files) => {
+                for file in files {
+                    push(file.name.clone(), file.read().map(Arc::from));
+                }
+            }
+            Err(e) => push(None, Err(e)),
+        }
+    }
+    // Solutions may use the global pool themselves, so jobs waiting for them can't block it.
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(schedule.threads)
+        .build()?;
+    let (sender, receiver) = mpsc::channel();
+    let count = jobs.len() * parts.len();
+    for job in jobs {
+        let sender = sender.clone();
+        let parts = parts.to_vec();
+        let timeout = schedule.timeout;
+        pool.spawn(move || run_job(year, job, &parts, timeout, &sender));
+    }
+    Ok(Outcomes {
+        _pool: pool,
+        receiver,
+        finished: HashMap::new(),
+        next: 0,
+        count,
+    })
+}
+
+fn run_job(
+    year: u16,
+    job: Job,
+    parts: &[Part],
+    timeout: Option<Duration>,
+    sender: &Sender<(usize, Outcome)>,
+) {
+    let Job {
+        day,
+        input,
+        text,
+        first,
+    } = job;
+    let outcomes = match text {
+        Ok(text) => {
+            let owned_parts = parts.to_vec();
+            run_with_timeout(year, day, parts, input, timeout, move |report| {
+                run_parts(year, day, &owned_parts, &text, &[], None, report)
+            })
+        }
+        Err(e) => parts
+            .iter()
+            .map(|&part| Outcome::failed(year, day, part, input.clone(), e.clone()))
+            .collect(),
+    };
+    for (i, outcome) in outcomes.into_iter().enumerate() {
+        // Outcomes are no longer needed when the receiver is gone.
+        let _ = sender.send((first + i, outcome));
+    }
+}
+
+/// Runs `run` on its own thread, waiting for it to report an outcome for each part. The
+/// timeout applies to each part separately, with parsing counted towards the first one. Once a
+/// part times out, the remaining ones are reported as timed out too.
+fn run_with_timeout(
+    year: u16,
+    day: u8,
+    parts: &[Part],
+    input: Option<String>,
+    timeout: Option<Duration>,
+    run: impl FnOnce(&mut dyn FnMut(Outcome)) + Send + 'static,
+) -> Vec<Outcome> {
+    // Solutions run on their own threads to be able to abandon them after a timeout.
+    let (sender, receiver) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name(format!("day {}", day))
+        .stack_size(SOLUTION_STACK_SIZE)
+        .spawn(move || {
+            // The receiver is gone if the job timed out.
+            run(&mut |outcome| {
+                let _ = sender.send(outcome);
+            })
+        });
+    if let Err(e) = spawned {
+        let failure = Failure::from_error(&e);
+        return parts
+            .iter()
+            .map(|&part| Outcome::failed(year, day, part, input.clone(), failure.clone()))
+            .collect();
+    }
+    let mut outcomes = Vec::new();
+    let mut abandoned: Option<&str> = None;
+    for &part in parts {
+        if let Some(failure) = &abandoned {
+            outcomes.push(Outcome::failed(
+                year,
+                day,
+                part,
+                input.clone(),
+                Failure::from(failure.to_string()),
+            ));
+            continue;
+        }
+        let received = match timeout {
+            Some(timeout) => receiver.recv_timeout(timeout),
+            None => receiver.recv().map_err(RecvTimeoutError::from),
+        };
+        outcomes.push(match received {
+            Ok(outcome) => Outcome {
+                input: input.clone(),
+                ..outcome
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                abandoned = Some("timed out");
+                Outcome {
+                    elapsed: timeout.unwrap_or_default(),
+                    ..Outcome::failed(
+                        year,
+                        day,
+                        part,
+                        input.clone(),
+                        "timed out".to_string().into(),
+                    )
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                abandoned = Some("panicked");
+                Outcome::failed(
+                    year,
+                    day,
+                    part,
+                    input.clone(),
+                    "panicked".to_string().into(),
+                )
+            }
+        });
+    }
+    outcomes
+}
+
+/// Some solutions are deeply recursive, this is the stack size of the main thread on Linux.
+const SOLUTION_STACK_SIZE: usize = 8 << 20;
+
+/// Outcomes of jobs ran in parallel, yielded in the order the jobs were scheduled.
+pub struct Outcomes {
+    _pool: ThreadPool,
+    receiver: Receiver<(usize, Outcome)>,
+    finished: HashMap<usize, Outcome>,
+    next: usize,
+    count: usize,
+}
+
+impl Iterator for Outcomes {
+    type Item = Outcome;
+
+    fn next(&mut self) -> Option<Outcome> {
+        if self.next == self.count {
+            return None;
+        }
+        while !self.finished.contains_key(&self.next) {
+            let (i, outcome) = self
+                .receiver
+                .recv()
+                .expect("Job was dropped without an outcome");
+            self.finished.insert(i, outcome);
+        }
+        self.next += 1;
+        self.finished.remove(&(self.next - 1))
+    }
+}
+
+/// Parses an input once and runs parts on it, reporting outcomes as soon as they're known.
+pub fn run_parts(
+    year: u16,
+    day: u8,
+    parts: &[Part],
+    input: &str,
+    overrides: &[(String, i64)],
+    trace: Option<&Trace>,
+    report: &mut dyn FnMut(Outcome),
+) {
+    let prepared = get_solution(year, day)
+        .and_then(|solution| Ok((solution, Parameters::new(solution.parameters(), overrides)?)));
+    let (solution, parameters) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            for &part in parts {
+                report(Outcome::failed(year, day, part, None, e.clone().into()));
+            }
+            return;
+        }
+    };
+    let start = Instant::now();
+    let parsed = solution.parse(input);
+    let parse_elapsed = start.elapsed();
+    let parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let failure = Failure::from_solution_error(e, day);
+            for &part in parts {
+                report(Outcome {
+                    parse_elapsed,
+                    ..Outcome::failed(year, day, part, None, failure.clone())
+                });
+            }
+            return;
+        }
+    };
+    for &part in parts {
+        let start = Instant::now();
+        let answer = match trace {
+            Some(trace) => parsed.trace(part, &parameters, trace).map(Answer::from),
+            None => parsed.answer(part, &parameters),
+        };
+        let (result, details) = match answer {
+            Ok(answer) => (Ok(answer.answer), answer.details),
+            Err(e) => (Err(Failure::from_solution_error(e, day)), None),
+        };
+        report(Outcome {
+            year,
+            day,
+            part,
+            input: None,
+            result,
+            details,
+            parse_elapsed,
+            elapsed: start.elapsed(),
+        });
+    }
+}
+
+pub fn run_part(
+    year: u16,
+    day: u8,
+    part: Part,
+    input: &str,
+    overrides: &[(String, i64)],
+) -> Outcome {
+    let mut outcome = None;
+    run_parts(year, day, &[part], input, overrides, None, &mut |o| {
+        outcome = Some(o)
+    });
+    outcome.expect("Part wasn't ran")
+}
+
+pub fn write_table(mut out: impl Write, outcomes: &[Outcome]) -> io::Result<()> {
+    // The year and input columns are only shown when they tell outcomes apart.
+    let years = outcomes.iter().map(|o| o.year).unique().count() > 1;
+    let named = outcomes.iter().any(|o| o.input.is_some());
+    let mut header = vec![];
+    if years {
+        header.push("Year");
+    }
+    header.extend(["Day", "Part"]);
+    if named {
+        header.push("Input");
+    }
+    header.extend(["Answer", "Parse", "Time", "Status"]);
+    let answer_column = header.len() - 4;
+    let rows: Vec<_> = outcomes
+        .iter()
+        .map(|outcome| {
+            let (answer, status) = match &outcome.result {
+                Ok(answer) => (answer.trim_matches('\n'), "ok".to_string()),
+                Err(e) => ("", format!("error: {}", e)),
+            };
+            let mut row = vec![];
+            if years {
+                row.push(outcome.year.to_string());
+            }
+            row.extend([outcome.day.to_string(), outcome.part.to_string()]);
+            if named {
+                row.push(outcome.input.clone().unwrap_or_default());
+            }
+            row.extend([
+                answer.to_string(),
+                format!("{:.2?}", outcome.parse_elapsed),
+                format!("{:.2?}", outcome.elapsed),
+                status,
+            ]);
+            row
+        })
+        .collect();
+    let left_aligned: Vec<_> = header
+        .iter()
+        .map(|h| matches!(*h, "Input" | "Answer"))
+        .collect();
+    let header: Vec<_> = header.into_iter().map(String::from).collect();
+    // Durations contain multi-byte characters, widths are counted in characters.
+    let mut widths: Vec<_> = header.iter().map(String::len).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = cell
+                .lines()
+                .map(|line| line.chars().count())
+                .fold(*width, usize::max);
+        }
+    }
+    let answer_indent: usize = widths[..answer_column].iter().map(|w| w + 2).sum();
+    for row in std::iter::once(&header).chain(&rows) {
+        let mut answer_lines = row[answer_column].lines();
+        for (i, (cell, &width)) in row.iter().zip(&widths).enumerate() {
+            let cell = if i == answer_column {
+                answer_lines.next().unwrap_or("")
+            } else {
+                cell
+            };
+            if i == row.len() - 1 {
+                writeln!(out, "{}", cell)?;
+            } else if left_aligned[i] {
+                write!(out, "{:<w$}  ", cell, w = width)?;
+            } else {
+                write!(out, "{:>w$}  ", cell, w = width)?;
+            }
+        }
+        for line in answer_lines {
+            writeln!(out, "{:w$}{}", "", line, w = answer_indent)?;
+        }
+    }
+    for outcome in outcomes {
+        if let Err(failure @ Failure { span: Some(_), .. }) = &outcome.result {
+            writeln!(out)?;
+            failure.write_report(&mut out)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_json(mut out: impl Write, outcomes: &[Outcome]) -> io::Result<()> {
+    let records: Vec<_> = outcomes.iter().map(Outcome::record).collect();
+    serde_json::to_writer_pretty(&mut out, &records)?;
+    writeln!(out)
+}
+
+pub fn write_ndjson(mut out: impl Write, outcomes: &[Outcome]) -> io::Result<()> {
+    for outcome in outcomes {
+        serde_json::to_writer(&mut out, &outcome.record())?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub fn write_outcomes(out: impl Write, format: Format, outcomes: &[Outcome]) -> io::Result<()> {
+    match format {
+        Format::Text => write_table(out, outcomes),
+        Format::Json => write_json(out, outcomes),
+        Format::Ndjson => write_ndjson(out, outcomes),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        parse_days, run_days, run_part, run_parts, run_with_timeout, write_json, write_ndjson,
+        write_table, Failure, Outcome, Part, Schedule,
+    };
+    use crate::inputs::BUNDLED;
+    use crate::lines;
+    use serde_json::{json, Value};
+    use std::error::Error;
+    use std::fmt::{self, Display, Formatter};
+    use std::num::ParseIntError;
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    struct InvalidSerial(ParseIntError);
+
+    impl Display for InvalidSerial {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "Invalid serial number")
+        }
+    }
+
+    impl Error for InvalidSerial {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn parses_day_ranges() {
+        assert_eq!(parse_days("3..=9").unwrap(), 3..=9);
+        assert_eq!(parse_days("3..9").unwrap(), 3..=8);
+        assert_eq!(parse_days("7").unwrap(), 7..=7);
+        assert!(parse_days("0..=3").is_err());
+        assert!(parse_days("20..=26").is_err());
+        assert!(parse_days("9..=3").is_err());
+        assert!(parse_days("a..=3").is_err());
+    }
+
+    #[test]
+    fn runs_days_in_parallel_in_order() {
+        let schedule = Schedule {
+            threads: 4,
+            timeout: None,
+        };
+        let outcomes: Vec<_> = run_days(2018, 8..=9, &Part::ALL, Path::new(BUNDLED), &schedule)
+            .unwrap()
+            .map(|o| (o.day, o.part, o.result.map_err(|e| e.message)))
+            .collect();
+        let missing = format!("No input for day 9 in {}", BUNDLED);
+        assert_eq!(
+            outcomes,
+            [
+                (8, Part::One, Ok("40036".into())),
+                (8, Part::Two, Ok("21677".into())),
+                (9, Part::One, Err(missing.clone())),
+                (9, Part::Two, Err(missing)),
+            ]
+        );
+    }
+
+    #[test]
+    fn abandons_jobs_after_timeout() {
+        let timeout = Some(Duration::from_millis(10));
+        let outcomes = run_with_timeout(2018, 1, &Part::ALL, None, timeout, |report| {
+            report(run_part(2018, 1, Part::One, "+1", &[]));
+            thread::sleep(Duration::from_secs(1));
+            report(run_part(2018, 1, Part::Two, "+1", &[]));
+        });
+        let results: Vec<_> = outcomes
+            .into_iter()
+            .map(|o| o.result.map_err(|e| e.message))
+            .collect();
+        assert_eq!(results, [Ok("1".into()), Err("timed out".into())],);
+        let outcomes =
+            run_with_timeout(2018, 1, &Part::ALL, Some("bob".into()), timeout, |report| {
+                run_parts(2018, 1, &Part::ALL, "+1\n-1", &[], None, report)
+            });
+        assert_eq!(outcomes[1].input.as_deref(), Some("bob"));
+        assert_eq!(outcomes[1].result.as_ref().unwrap(), "0");
+    }
+
+    #[test]
+    fn table_keeps_going_after_errors() {
+        let outcomes = [
+            Outcome {
+                year: 2018,
+                day: 1,
+                part: Part::One,
+                input: None,
+                result: Ok("430".into()),
+                details: None,
+                parse_elapsed: Duration::from_micros(5),
+                elapsed: Duration::from_millis(1),
+            },
+            Outcome {
+                year: 2018,
+                day: 1,
+                part: Part::Two,
+                input: None,
+                result: Err(Failure::from("Empty input".to_string())),
+                details: None,
+                parse_elapsed: Duration::from_micros(5),
+                elapsed: Duration::from_millis(2),
+            },
+            Outcome {
+                year: 2018,
+                day: 10,
+                part: Part::One,
+                input: None,
+                result: Ok("\n#..#\n####\n".into()),
+                details: None,
+                parse_elapsed: Duration::from_micros(5),
+                elapsed: Duration::from_millis(3),
+            },
+        ];
+        let mut out = Vec::new();
+        write_table(&mut out, &outcomes).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            lines!(
+                "Day  Part  Answer   Parse    Time  Status"
+                "  1     1  430     5.00µs  1.00ms  ok"
+                "  1     2          5.00µs  2.00ms  error: Empty input"
+                " 10     1  #..#    5.00µs  3.00ms  ok"
+                "           ####"
+            )
+        );
+    }
+
+    #[test]
+    fn table_shows_names_of_inputs() {
+        let outcome = |input: Option<&str>, answer: &str| Outcome {
+            year: 2018,
+            day: 7,
+            part: Part::One,
+            input: input.map(String::from),
+            result: Ok(answer.into()),
+            details: None,
+            parse_elapsed: Duration::from_micros(5),
+            elapsed: Duration::from_millis(1),
+        };
+        let outcomes = [
+            outcome(None, "ABC"),
+            outcome(Some("alice"), "CAB"),
+            outcome(Some("bob"), "BCA"),
+        ];
+        let mut out = Vec::new();
+        write_table(&mut out, &outcomes).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            lines!(
+                "Day  Part  Input  Answer   Parse    Time  Status"
+                "  7     1         ABC     5.00µs  1.00ms  ok"
+                "  7     1  alice  CAB     5.00µs  1.00ms  ok"
+                "  7     1  bob    BCA     5.00µs  1.00ms  ok"
+            )
+        );
+    }
+
+    #[test]
+    fn table_points_at_input_errors() {
+        // Day 4 sorts its lines before parsing them, which mustn't affect reported lines.
+        let input = lines!(
+            "[1518-11-01 00:05] falls asleep"
+            "[1518-11-01 00:00] Guard #10 begins shift"
+            "[1518-11-01 00:25] wakes upp"
+        );
+        let outcomes = [run_part(2018, 4, Part::One, input, &[])];
+        let mut out = Vec::new();
+        write_table(&mut out, &outcomes).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let (table, report) = out.split_once("\n\n").unwrap();
+        assert!(table.ends_with("error: day 4, line 3, column 28: expected end of line, found 'p'"));
+        assert_eq!(
+            report,
+            lines!(
+                "error: day 4, line 3, column 28: expected end of line, found 'p'"
+                "  |"
+                "3 | [1518-11-01 00:25] wakes upp"
+                "  |                            ^"
+            )
+        );
+    }
+
+    #[test]
+    fn json_keeps_multiline_answers_and_error_sources() {
+        let outcomes = [
+            Outcome {
+                year: 2018,
+                day: 10,
+                part: Part::One,
+                input: None,
+                result: Ok("\n#..#\n####\n".into()),
+                details: None,
+                parse_elapsed: Duration::from_nanos(7),
+                elapsed: Duration::from_nanos(1234),
+            },
+            Outcome {
+                year: 2018,
+                day: 3,
+                part: Part::Two,
+                input: None,
+                result: Err(Failure::from_error(&InvalidSerial(
+                    "x".parse::<u8>().unwrap_err(),
+                ))),
+                details: None,
+                parse_elapsed: Duration::from_nanos(7),
+                elapsed: Duration::from_nanos(5),
+            },
+        ];
+        let mut out = Vec::new();
+        write_json(&mut out, &outcomes).unwrap();
+        let value: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                {
+                    "year": 2018,
+                    "day": 10,
+                    "part": 1,
+                    "answer": "\n#..#\n####\n",
+                    "parse_ns": 7,
+                    "duration_ns": 1234,
+                    "error": null,
+                },
+                {
+                    "year": 2018,
+                    "day": 3,
+                    "part": 2,
+                    "answer": null,
+                    "parse_ns": 7,
+                    "duration_ns": 5,
+                    "error": {
+                        "message": "Invalid serial number",
+                        "sources": ["invalid digit found in string"],
+                    },
+                },
+            ])
+        );
+        let mut out = Vec::new();
+        write_ndjson(&mut out, &outcomes).unwrap();
+        let lines: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(Value::Array(lines), value);
+    }
+
+    #[test]
+    fn json_shows_details_of_answers() {
+        let input = lines!(
+            "#######"
+            "#.G...#"
+            "#...EG#"
+            "#.#.#G#"
+            "#..G#E#"
+            "#.....#"
+            "#######"
+        );
+        let outcomes = [run_part(2018, 15, Part::Two, input, &[])];
+        let mut out = Vec::new();
+        write_json(&mut out, &outcomes).unwrap();
+        let value: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value[0]["answer"], "4988");
+        assert_eq!(
+            value[0]["details"],
+            json!({"power": 15, "rounds": 29, "hit_points": 172})
+        );
+    }
+}
@@ -8,7 +8,9 @@ macro_rules! test {
         $($tests:tt)*
     ) => {
         #[test] $( #[ $meta ] )* fn input() {
-            assert_eq!(($ident.$part)(include_str!("input")).unwrap(), concat!($output));
+            let parameters = $crate::parameters::Parameters::defaults($ident::PARAMETERS);
+            let input = $ident::parse(include_str!("input")).unwrap();
+            assert_eq!($ident::$part(&input, &parameters).unwrap(), concat!($output));
         }
         super::test!(__internal $ident.$part $($tests)*);
     };
@@ -21,7 +23,9 @@ macro_rules! test {
         $($tests:tt)*
     ) => {
         #[test] $( #[ $meta ] )* fn $name() {
-            assert_eq!(($ident.$part)($input).unwrap(), concat!($output));
+            let parameters = $crate::parameters::Parameters::defaults($ident::PARAMETERS);
+            let input = $ident::parse($input).unwrap();
+            assert_eq!($ident::$part(&input, &parameters).unwrap(), concat!($output));
         }
         super::test!(__internal $ident.$part $($tests)*);
     };
@@ -40,7 +44,11 @@ macro_rules! test {
     ) => {
         mod $part {
             #[allow(unused_imports)]
-            use crate::lines;
+            use $crate::lines;
+            #[allow(unused_imports)]
+            use $crate::puzzle::Puzzle;
+            #[allow(unused_imports)]
+            use $crate::runner::Part;
             #[allow(unused_imports)]
             use super::super::$ident;
             super::test!(__internal $ident.$part $($tests)*);
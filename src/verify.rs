@@ -0,0 +1,247 @@
+use crate::runner::{self, Failure, Part};
+use itertools::{EitherOrBoth, Itertools};
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// List of known answers. Input paths are relative to the directory containing the manifest.
+///
+/// In TOML it's written as a sequence of `[[answers]]` tables with `day`, `part`, `input` and
+/// `answer` keys, and in JSON as `{"answers": [{"day": 1, ...}]}`. An optional `year` key
+/// defaults to the most recent year.
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    answers: Vec<Expectation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Expectation {
+    year: Option<u16>,
+    day: u8,
+    part: Part,
+    input: PathBuf,
+    answer: Answer,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Answer {
+    Text(String),
+    Integer(i64),
+}
+
+impl Answer {
+    fn to_text(&self) -> String {
+        match self {
+            Answer::Text(text) => text.clone(),
+            Answer::Integer(integer) => integer.to_string(),
+        }
+    }
+}
+
+pub fn load_manifest(path: &Path) -> Result<Manifest, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    if path.extension().is_some_and(|e| e == "toml") {
+        Ok(toml::from_str(&text)?)
+    } else {
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+pub struct Check {
+    pub day: u8,
+    pub part: Part,
+    pub input: PathBuf,
+    pub verdict: Verdict,
+}
+
+pub enum Verdict {
+    Pass,
+    Mismatch { expected: String, actual: String },
+    Fail(Failure),
+}
+
+pub fn verify(manifest: &Manifest, directory: &Path) -> Vec<Check> {
+    manifest
+        .answers
+        .iter()
+        .map(|expectation| {
+            let Expectation {
+                year,
+                day,
+                part,
+                ref input,
+                ref answer,
+            } = *expectation;
+            let path = directory.join(input);
+            let verdict = match fs::read_to_string(&path) {
+                Ok(text) => match runner::run_part(
+                    year.unwrap_or_else(runner::default_year),
+                    day,
+                    part,
+                    &text,
+                    &[],
+                )
+                .result
+                {
+                    Ok(actual) => {
+                        let expected = answer.to_text();
+                        // Multi-line answers start with a newline to make them readable in tests,
+                        // which is inconvenient to write in manifests.
+                        if actual.trim_matches('\n') == expected.trim_matches('\n') {
+                            Verdict::Pass
+                        } else {
+                            Verdict::Mismatch { expected, actual }
+                        }
+                    }
+                    Err(e) => Verdict::Fail(e),
+                },
+                Err(e) => Verdict::Fail(Failure {
+                    message: format!("Cannot read {}", path.display()),
+                    sources: vec![e.to_string()],
+                    span: None,
+                }),
+            };
+            Check {
+                day,
+                part,
+                input: input.clone(),
+                verdict,
+            }
+        })
+        .collect()
+}
+
+/// Writes a report, returning the number of checks that didn't pass.
+pub fn write_report(mut out: impl Write, checks: &[Check]) -> io::Result<usize> {
+    let mut failures = 0;
+    for Check {
+        day,
+        part,
+        input,
+        verdict,
+    } in checks
+    {
+        let status = match verdict {
+            Verdict::Pass => "PASS",
+            Verdict::Mismatch { .. } => "MISMATCH",
+            Verdict::Fail(_) => "FAIL",
+        };
+        write!(
+            out,
+            "{:<8}  day {:>2} part {}  {}",
+            status,
+            day,
+            part,
+            input.display()
+        )?;
+        match verdict {
+            Verdict::Pass => writeln!(out)?,
+            Verdict::Mismatch { expected, actual } => {
+                failures += 1;
+                writeln!(out)?;
+                let expected = expected.trim_matches('\n').lines();
+                let actual = actual.trim_matches('\n').lines();
+                for lines in expected.zip_longest(actual) {
+                    match lines {
+                        EitherOrBoth::Both(expected, actual) if expected == actual => {
+                            writeln!(out, "      {}", expected)?
+                        }
+                        EitherOrBoth::Both(expected, actual) => {
+                            writeln!(out, "    - {}", expected)?;
+                            writeln!(out, "    + {}", actual)?;
+                        }
+                        EitherOrBoth::Left(expected) => writeln!(out, "    - {}", expected)?,
+                        EitherOrBoth::Right(actual) => writeln!(out, "    + {}", actual)?,
+                    }
+                }
+            }
+            Verdict::Fail(failure) => {
+                failures += 1;
+                writeln!(out, ": {}", failure)?;
+                if let Some(span) = &failure.span {
+                    span.write_snippet(&mut out)?;
+                }
+            }
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{verify, write_report, Manifest};
+    use crate::inputs::BUNDLED;
+    use crate::lines;
+    use std::path::Path;
+
+    fn check(manifest: Manifest) -> (usize, String) {
+        let checks = verify(&manifest, Path::new(BUNDLED));
+        let mut out = Vec::new();
+        let failures = write_report(&mut out, &checks).unwrap();
+        (failures, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn verifies_toml_manifest() {
+        let manifest = toml::from_str(lines!(
+            "[[answers]]"
+            "day = 1"
+            "part = 1"
+            "input = 'day1/input'"
+            "answer = 430"
+            ""
+            "[[answers]]"
+            "day = 2"
+            "part = 2"
+            "input = 'day2/input'"
+            "answer = 'lnfqdscwjyteorambzuchrgpx'"
+        ))
+        .unwrap();
+        assert_eq!(
+            check(manifest),
+            (
+                0,
+                lines!(
+                    "PASS      day  1 part 1  day1/input"
+                    "PASS      day  2 part 2  day2/input"
+                )
+                .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn reports_mismatches_and_failures() {
+        let manifest = serde_json::from_str(
+            r#"{"answers": [
+                {"day": 1, "part": 2, "input": "day1/input", "answer": 463},
+                {"day": 9, "part": 1, "input": "day9/input", "answer": "0"},
+                {"day": 1, "part": 1, "input": "day2/input", "answer": "0"}
+            ]}"#,
+        )
+        .unwrap();
+        let (failures, report) = check(manifest);
+        assert_eq!(failures, 3);
+        let mut lines = report.lines();
+        assert_eq!(lines.next().unwrap(), "MISMATCH  day  1 part 2  day1/input");
+        assert_eq!(lines.next().unwrap(), "    - 463");
+        assert_eq!(lines.next().unwrap(), "    + 462");
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("FAIL      day  9 part 1  day9/input: Cannot read "));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("FAIL      day  1 part 1  day2/input: invalid digit"));
+    }
+
+    #[test]
+    fn rejects_unknown_parts() {
+        let manifest = r#"{"answers": [{"day": 1, "part": 3, "input": "x", "answer": 1}]}"#;
+        assert!(serde_json::from_str::<Manifest>(manifest).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use advent_of_code_2018::cpu::{get_cpu, InstructionKind};
+use advent_of_code_2018::day15::{Game, Position};
+use advent_of_code_2018::day20::Maze;
+use advent_of_code_2018::parameters::Parameters;
+use advent_of_code_2018::registry;
+use advent_of_code_2018::runner::{self, Part};
+
+#[test]
+fn registry_has_every_day() {
+    assert_eq!(registry().latest_year(), Some(2018));
+    assert!(registry().days(2018).eq(1..=25));
+    let day1 = registry().get(2018, 1).unwrap();
+    assert_eq!(day1.title(), "Chronal Calibration");
+    let parameters = Parameters::defaults(day1.parameters());
+    let parsed = day1.parse("+1\n-2\n+3\n+1\n").unwrap();
+    assert_eq!(parsed.run(Part::One, &parameters).unwrap(), "3");
+    assert_eq!(parsed.run(Part::Two, &parameters).unwrap(), "2");
+}
+
+#[test]
+fn runner_runs_parts_with_overrides() {
+    let input = "Step C must be finished before step A can begin.\n";
+    let outcome = runner::run_part(2018, 7, Part::Two, input, &[("delay".into(), 0)]);
+    assert_eq!(outcome.result.unwrap(), "4");
+    let outcome = runner::run_part(2018, 7, Part::Two, input, &[("elves".into(), 0)]);
+    assert!(outcome.result.is_err());
+}
+
+#[test]
+fn cpu_runs_programs() {
+    let program = "#ip 0\nseti 5 0 1\nseti 6 0 2\naddi 0 1 0\naddr 1 2 3\nsetr 1 0 0\nseti 8 0 4\nseti 9 0 5\n";
+    let mut cpu = get_cpu(program, [0; 6]).unwrap();
+    assert_eq!(cpu.ip_register(), Ok(0));
+    assert_eq!(cpu.instructions().len(), 7);
+    let first = cpu.current_instruction().unwrap();
+    assert!(matches!(first.kind, InstructionKind::Seti));
+    assert_eq!(first.parameters, [5, 0, 1]);
+    while cpu.step() {}
+    assert_eq!(cpu.registers, [7, 5, 6, 0, 0, 9]);
+}
+
+#[test]
+fn game_simulates_combat() {
+    let board = "#######\n#.G...#\n#...EG#\n#.#.#G#\n#..G#E#\n#.....#\n#######\n";
+    let mut game = Game::new(board, 3);
+    assert_eq!(game.players().len(), 6);
+    assert!(game.take_turns());
+    let elf = game.players().iter().find(|p| p.faction() == 'E').unwrap();
+    assert_eq!(elf.position(), Position { x: 4, y: 2 });
+    let mut rounds = 1;
+    while game.take_turns() {
+        rounds += 1;
+    }
+    let hit_points: u32 = game
+        .players()
+        .iter()
+        .map(|p| u32::from(p.hit_points()))
+        .sum();
+    assert_eq!(rounds * hit_points, 27_730);
+}
+
+#[test]
+fn maze_finds_furthest_room() {
+    let maze = Maze::parse("^ENWWW(NEEE|SSE(EE|N))$").unwrap();
+    assert_eq!(maze.find_furthest_room().unwrap(), 10);
+    assert!(Maze::parse("ENWWW").is_err());
+}